@@ -0,0 +1,247 @@
+//! GitHub Actions integration: job summary, step outputs and workflow annotations.
+//!
+//! See <https://docs.github.com/en/actions/reference/workflow-commands-for-github-actions>.
+
+use std::path::Path;
+
+use tokio::io::AsyncWriteExt;
+use tracing::info;
+
+use crate::budget::regression_percentage;
+use crate::export::ResultExport;
+use crate::prelude::*;
+use crate::scene::{format_delta, format_percentage};
+
+/// Appends the comment to the job summary, writes the step outputs and prints an annotation per
+/// regression, for the GitHub Actions environment variables that are set.
+pub async fn publish(export: &ResultExport, comment: &str, comment_path: &Path) -> Result<()> {
+    if let Some(summary_path) = env_var("GITHUB_STEP_SUMMARY") {
+        append(&summary_path, &format!("{}\n", comment)).await?;
+        info!("Report appended to the job summary");
+    }
+    if let Some(output_path) = env_var("GITHUB_OUTPUT") {
+        let outputs: String = step_outputs(export, comment_path)
+            .into_iter()
+            .map(|(name, value)| format!("{}={}\n", name, value))
+            .collect();
+        append(&output_path, &outputs).await?;
+        info!("Step outputs written to {}", output_path);
+    }
+    if env_var("GITHUB_ACTIONS").is_some_and(|actions| actions == "true") {
+        for command in workflow_commands(export) {
+            println!("{}", command);
+        }
+    }
+    Ok(())
+}
+
+/// Outputs of the step: `regressions_count`, `worst_scene`, `baseline_sha` and `comment_path`.
+///
+/// Only the metrics classified in the export are counted, see
+/// [`crate::config::MetricsConfig::classified`]. `worst_scene` is the scene with the largest
+/// relative regression, empty without regressions.
+pub fn step_outputs(export: &ResultExport, comment_path: &Path) -> Vec<(&'static str, String)> {
+    let regressions = export.regressions();
+    let worst_scene = regressions
+        .iter()
+        .filter_map(|(_, change)| {
+            let regression = regression_percentage(&change.metric, change.value, change.delta)?;
+            Some((change, regression))
+        })
+        .max_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(change, _)| change.scene_name.clone());
+    vec![
+        ("regressions_count", regressions.len().to_string()),
+        ("worst_scene", single_line(&worst_scene.unwrap_or_default())),
+        (
+            "baseline_sha",
+            export
+                .baseline
+                .as_ref()
+                .map(|baseline| baseline.sha.clone())
+                .unwrap_or_default(),
+        ),
+        (
+            "comment_path",
+            single_line(&comment_path.display().to_string()),
+        ),
+    ]
+}
+
+/// An `::error::` command per budget violation and a `::warning::` command per other regression.
+pub fn workflow_commands(export: &ResultExport) -> Vec<String> {
+    let mut commands: Vec<String> = export
+        .violations
+        .iter()
+        .map(|violation| {
+            format!(
+                "::error title=Performance budget exceeded::{}",
+                escape_data(&violation.to_string())
+            )
+        })
+        .collect();
+    for (board_config, change) in export.regressions() {
+        if export.exceeds_budget(board_config, change) {
+            continue;
+        }
+        let mut message = format!(
+            "{}: '{}' {} changed by {}",
+            board_config.name,
+            change.scene_name,
+            change.metric.label(),
+            format_delta(change.delta)
+        );
+        if let Some(relative_delta) = change.relative_delta {
+            message += &format!(" ({})", format_percentage(relative_delta));
+        }
+        commands.push(format!(
+            "::warning title=Performance regression::{}",
+            escape_data(&message)
+        ));
+    }
+    commands
+}
+
+fn env_var(name: &str) -> Option<String> {
+    std::env::var(name).ok().filter(|value| !value.is_empty())
+}
+
+async fn append(path: &str, content: &str) -> Result<()> {
+    let mut file = tokio::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .await?;
+    file.write_all(content.as_bytes()).await?;
+    Ok(())
+}
+
+/// Escapes the message of a workflow command.
+fn escape_data(data: &str) -> String {
+    data.replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+/// Keeps a step output on a single line, as required by the `name=value` syntax.
+fn single_line(value: &str) -> String {
+    value.replace(['\r', '\n'], " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::budget::{Budget, BudgetCheck, check_budgets};
+    use crate::config::{MetricsConfig, SignificanceConfig, ThresholdConfig};
+    use crate::matching::BoardMatch;
+    use crate::result::BoardComparison;
+    use crate::result::fixtures::{
+        create_baseline, create_config, create_regressed_results, create_scene,
+    };
+    use crate::scene::{BenchmarkRun, SceneAliases, SceneMetric};
+    use std::path::PathBuf;
+    use uuid::Uuid;
+
+    #[test]
+    fn test_step_outputs() {
+        let results = create_regressed_results();
+        let baseline = create_baseline();
+        let export = ResultExport::new(
+            &results,
+            &[],
+            Some(&baseline),
+            &SceneMetric::PARSED,
+            &ThresholdConfig::default(),
+            &SignificanceConfig::default(),
+        );
+        assert_eq!(
+            step_outputs(&export, &PathBuf::from("comment.md")),
+            vec![
+                ("regressions_count", "2".to_string()),
+                ("worst_scene", "Widgets demo".to_string()),
+                ("baseline_sha", "0123456789abcdef".to_string()),
+                ("comment_path", "comment.md".to_string()),
+            ]
+        );
+
+        let export = ResultExport::new(
+            &[],
+            &[],
+            None,
+            &SceneMetric::PARSED,
+            &ThresholdConfig::default(),
+            &SignificanceConfig::default(),
+        );
+        let outputs = step_outputs(&export, &PathBuf::from("comment.md"));
+        assert_eq!(outputs[0], ("regressions_count", "0".to_string()));
+        assert_eq!(outputs[1], ("worst_scene", String::new()));
+        assert_eq!(outputs[2], ("baseline_sha", String::new()));
+    }
+
+    #[test]
+    fn test_workflow_commands() {
+        let results = create_regressed_results();
+        let budgets = vec![Budget::new(SceneMetric::FPS, 3.0)];
+        let violations: Vec<_> = check_budgets(
+            &results,
+            &budgets,
+            &SceneAliases::default(),
+            &SignificanceConfig::default(),
+        )
+        .iter()
+        .filter_map(BudgetCheck::violation)
+        .collect();
+        let export = ResultExport::new(
+            &results,
+            &violations,
+            None,
+            &SceneMetric::PARSED,
+            &ThresholdConfig::default(),
+            &SignificanceConfig::default(),
+        );
+
+        assert_eq!(
+            workflow_commands(&export),
+            vec![
+                "::error title=Performance budget exceeded::Board A: 'Empty screen' FPS changed by -5.0%25 (every scene: FPS must not drop more than 3%25)",
+                "::warning title=Performance regression::Board A: 'Widgets demo' Render Time (ms) changed by +10 (+100.0%25)",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_single_fps_drop_reported_once() {
+        let config = create_config(Uuid::new_v4(), "Board A", vec![]);
+        let metrics = MetricsConfig::default();
+        let mut scene = create_scene("Empty screen", 0.0, 30.0, 10.0, 5.0, 0.0);
+        let mut baseline_run = BenchmarkRun::new(vec![scene.clone()]);
+        scene.avg_fps = 27.0;
+        let mut run = BenchmarkRun::new(vec![scene]);
+        metrics.apply(&mut run).unwrap();
+        metrics.apply(&mut baseline_run).unwrap();
+        let comparison = BoardComparison::compared(
+            config,
+            run,
+            &baseline_run,
+            BoardMatch::Id,
+            &SceneAliases::default(),
+        );
+        let export = ResultExport::new(
+            &[comparison],
+            &[],
+            None,
+            &metrics.classified(&[]),
+            &ThresholdConfig::default(),
+            &SignificanceConfig::default(),
+        );
+
+        let outputs = step_outputs(&export, &PathBuf::from("comment.md"));
+        assert_eq!(outputs[0], ("regressions_count", "1".to_string()));
+        assert_eq!(
+            workflow_commands(&export),
+            vec![
+                "::warning title=Performance regression::Board A: 'Empty screen' FPS changed by -3 (-10.0%25)"
+            ]
+        );
+    }
+}
@@ -0,0 +1,619 @@
+//! Performance budgets checked against the computed deltas.
+
+use std::fmt;
+use std::path::Path;
+
+use ej_config::ej_board_config::EjBoardConfigApi;
+use globset::{GlobBuilder, GlobMatcher};
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use crate::config::SignificanceConfig;
+use crate::prelude::*;
+use crate::result::{BoardComparison, SceneComparison, relative_delta};
+use crate::scene::{MetricDirection, SceneAliases, SceneMetric, format_percentage};
+
+/// Maximum regression allowed for a metric, for example
+/// "All scenes avg. FPS must not drop more than 3%".
+///
+/// A budget applies to every board and scene unless it's restricted with `board`, `tag`,
+/// `scene` or `scene_regex`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Budget {
+    /// Name used to report the rule
+    pub name: Option<String>,
+    /// Board config name the budget applies to
+    pub board: Option<String>,
+    /// Board config tag the budget applies to
+    pub tag: Option<String>,
+    /// Glob matching the scenes the budget applies to
+    pub scene: Option<SceneGlob>,
+    /// Regex matching the scenes the budget applies to
+    pub scene_regex: Option<SceneRegex>,
+    pub metric: SceneMetric,
+    /// Maximum regression in percent of the baseline value
+    pub max_regression: f64,
+}
+
+/// Scene name glob, such as `Containers*`, matched ignoring case.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct SceneGlob(GlobMatcher);
+
+/// Scene name regex, matched against the whole scene name ignoring case.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct SceneRegex(Regex);
+
+/// Content of a budgets file, a list of `[[budgets]]` tables.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct BudgetFile {
+    budgets: Vec<Budget>,
+}
+
+/// A scene metric that regressed more than a [`Budget`] allows.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BudgetViolation {
+    pub board_config: EjBoardConfigApi,
+    pub scene_name: String,
+    pub metric: SceneMetric,
+    /// Relative change of the metric, in percent
+    pub change: f64,
+    /// The budget rule that was exceeded
+    pub budget: Budget,
+}
+
+/// A scene metric checked against the [`Budget`] that applies to it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BudgetCheck {
+    pub board_config: EjBoardConfigApi,
+    pub scene_name: String,
+    pub metric: SceneMetric,
+    pub value: f64,
+    pub baseline: f64,
+    /// Relative change of the metric in percent, `None` when the baseline is zero
+    pub change: Option<f64>,
+    pub p_value: Option<f64>,
+    pub budget: Budget,
+    /// Whether the change is a significant regression larger than the budget allows
+    pub exceeded: bool,
+}
+
+impl TryFrom<String> for SceneGlob {
+    type Error = globset::Error;
+
+    fn try_from(glob: String) -> core::result::Result<Self, Self::Error> {
+        let glob = GlobBuilder::new(&glob).case_insensitive(true).build()?;
+        Ok(Self(glob.compile_matcher()))
+    }
+}
+
+impl From<SceneGlob> for String {
+    fn from(glob: SceneGlob) -> Self {
+        glob.0.glob().glob().to_string()
+    }
+}
+
+impl PartialEq for SceneGlob {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.glob() == other.0.glob()
+    }
+}
+
+impl TryFrom<String> for SceneRegex {
+    type Error = regex::Error;
+
+    fn try_from(regex: String) -> core::result::Result<Self, Self::Error> {
+        let regex = RegexBuilder::new(&format!("^(?:{})$", regex))
+            .case_insensitive(true)
+            .build()?;
+        Ok(Self(regex))
+    }
+}
+
+impl From<SceneRegex> for String {
+    fn from(regex: SceneRegex) -> Self {
+        regex.to_string()
+    }
+}
+
+impl fmt::Display for SceneRegex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let anchored = self.0.as_str();
+        let regex = anchored
+            .strip_prefix("^(?:")
+            .and_then(|regex| regex.strip_suffix(")$"))
+            .unwrap_or(anchored);
+        write!(f, "{}", regex)
+    }
+}
+
+impl PartialEq for SceneRegex {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.as_str() == other.0.as_str()
+    }
+}
+
+impl Budget {
+    /// Budget applying to every board and scene.
+    pub fn new(metric: SceneMetric, max_regression: f64) -> Self {
+        Self {
+            name: None,
+            board: None,
+            tag: None,
+            scene: None,
+            scene_regex: None,
+            metric,
+            max_regression,
+        }
+    }
+
+    /// Whether the budget applies to a scene of a board.
+    ///
+    /// Scene patterns match the scene name as printed, its normalized name or any of its
+    /// `aliases`, so a budget keeps applying to a renamed scene.
+    pub fn applies_to(
+        &self,
+        board_config: &EjBoardConfigApi,
+        scene_name: &str,
+        aliases: &SceneAliases,
+    ) -> bool {
+        let names = aliases.names(scene_name);
+        self.board
+            .as_ref()
+            .is_none_or(|board| *board == board_config.name)
+            && self
+                .tag
+                .as_ref()
+                .is_none_or(|tag| board_config.tags.contains(tag))
+            && self
+                .scene
+                .as_ref()
+                .is_none_or(|glob| names.iter().any(|name| glob.0.is_match(name)))
+            && self
+                .scene_regex
+                .as_ref()
+                .is_none_or(|regex| names.iter().any(|name| regex.0.is_match(name)))
+    }
+
+    /// Precedence of the budget among the budgets matching a scene metric, higher wins.
+    ///
+    /// Budgets with more restrictions win. Among equally restricted budgets, a scene pattern wins
+    /// over a board name, and a board name over a tag.
+    fn precedence(&self) -> (usize, bool, bool, bool) {
+        let restrictions = [
+            self.board.is_some(),
+            self.tag.is_some(),
+            self.scene.is_some(),
+            self.scene_regex.is_some(),
+        ]
+        .into_iter()
+        .filter(|restricted| *restricted)
+        .count();
+        let scene = self.scene.is_some() || self.scene_regex.is_some();
+        (
+            restrictions,
+            scene,
+            self.board.is_some(),
+            self.tag.is_some(),
+        )
+    }
+
+    /// Boards and scenes the budget applies to.
+    fn scope(&self) -> String {
+        let mut scope = Vec::new();
+        if let Some(board) = &self.board {
+            scope.push(board.clone());
+        }
+        if let Some(tag) = &self.tag {
+            scope.push(format!("[{}]", tag));
+        }
+        if let Some(glob) = &self.scene {
+            scope.push(String::from(glob.clone()));
+        }
+        if let Some(regex) = &self.scene_regex {
+            scope.push(format!("/{}/", regex));
+        }
+        if self.scene.is_none() && self.scene_regex.is_none() {
+            scope.push("every scene".to_string());
+        }
+        scope.join(" ")
+    }
+}
+
+impl fmt::Display for Budget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(name) = &self.name {
+            write!(f, "{}: ", name)?;
+        }
+        let direction = match self.metric.direction() {
+            MetricDirection::HigherIsBetter => "drop",
+            MetricDirection::LowerIsBetter => "rise",
+        };
+        write!(
+            f,
+            "{}: {} must not {} more than {}%",
+            self.scope(),
+            self.metric.label(),
+            direction,
+            self.max_regression
+        )
+    }
+}
+
+impl fmt::Display for BudgetViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: '{}' {} changed by {} ({})",
+            self.board_config.name,
+            self.scene_name,
+            self.metric.label(),
+            format_percentage(self.change),
+            self.budget
+        )
+    }
+}
+
+/// Loads the budgets of a budgets file.
+pub fn load_budgets(path: &Path) -> Result<Vec<Budget>> {
+    info!("Loading budgets from {}", path.display());
+    let content = std::fs::read_to_string(path)?;
+    let file: BudgetFile =
+        toml::from_str(&content).map_err(|err| Error::InvalidConfig(path.to_path_buf(), err))?;
+    Ok(file.budgets)
+}
+
+/// How much worse `value` is than its baseline, in percent. Negative values are improvements.
+pub fn regression_percentage(metric: &SceneMetric, value: f64, delta: f64) -> Option<f64> {
+    let relative = relative_delta(value, delta)?;
+    match metric.direction() {
+        MetricDirection::HigherIsBetter => Some(-relative),
+        MetricDirection::LowerIsBetter => Some(relative),
+    }
+}
+
+/// Finds the budget of a board scene metric.
+///
+/// When several budgets match, the one with the highest [`Budget::precedence`] wins, and the first
+/// one defined among budgets with the same precedence.
+fn find_budget<'a>(
+    budgets: &'a [Budget],
+    board_config: &EjBoardConfigApi,
+    scene_name: &str,
+    metric: &SceneMetric,
+    aliases: &SceneAliases,
+) -> Option<&'a Budget> {
+    budgets
+        .iter()
+        .filter(|budget| {
+            budget.metric == *metric && budget.applies_to(board_config, scene_name, aliases)
+        })
+        // `max_by_key` returns the last maximum, so iterate backwards for the first one defined
+        .rev()
+        .max_by_key(|budget| budget.precedence())
+}
+
+impl BudgetCheck {
+    pub fn violation(&self) -> Option<BudgetViolation> {
+        self.exceeded.then(|| BudgetViolation {
+            board_config: self.board_config.clone(),
+            scene_name: self.scene_name.clone(),
+            metric: self.metric.clone(),
+            change: self.change.unwrap_or_default(),
+            budget: self.budget.clone(),
+        })
+    }
+}
+
+/// Checks every scene metric of every run compared with a baseline that has a budget.
+///
+/// Only scenes compared with a baseline can regress, and changes that aren't statistically
+/// significant never exceed their budget. Budgets apply to scenes through their `aliases` too.
+pub fn check_budgets(
+    results: &[BoardComparison],
+    budgets: &[Budget],
+    aliases: &SceneAliases,
+    significance: &SignificanceConfig,
+) -> Vec<BudgetCheck> {
+    let mut checks = Vec::new();
+    for comparison in results {
+        let (board_config, run) = (&comparison.board_config, &comparison.run);
+        for scene_comparison in &comparison.scenes {
+            let SceneComparison::Compared {
+                scene,
+                delta: scene_delta,
+            } = scene_comparison
+            else {
+                continue;
+            };
+            for metric in &SceneMetric::ALL {
+                let Some(budget) =
+                    find_budget(budgets, board_config, &scene.scene_name, metric, aliases)
+                else {
+                    continue;
+                };
+                let value = scene.get_value(metric);
+                let delta = scene_delta.get_value(metric);
+                let p_value = run
+                    .get_stats(&scene.scene_name, metric)
+                    .and_then(|stats| stats.p_value);
+                let exceeded =
+                    regression_percentage(metric, value, delta).is_some_and(|regression| {
+                        regression > budget.max_regression && significance.is_significant(p_value)
+                    });
+                checks.push(BudgetCheck {
+                    board_config: board_config.clone(),
+                    scene_name: scene.scene_name.clone(),
+                    metric: metric.clone(),
+                    value,
+                    baseline: value - delta,
+                    change: relative_delta(value, delta),
+                    p_value,
+                    budget: budget.clone(),
+                    exceeded,
+                });
+            }
+        }
+    }
+    checks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::result::fixtures::{create_config, create_results, create_scene};
+    use crate::scene::BenchmarkRun;
+    use uuid::Uuid;
+
+    /// Keeps the budget violations of [`check_budgets`].
+    fn evaluate_budgets(
+        results: &[BoardComparison],
+        budgets: &[Budget],
+        significance: &SignificanceConfig,
+    ) -> Vec<BudgetViolation> {
+        check_budgets(results, budgets, &SceneAliases::default(), significance)
+            .iter()
+            .filter_map(BudgetCheck::violation)
+            .collect()
+    }
+
+    fn parse_budget(budget: &str) -> Budget {
+        toml::from_str(budget).expect("Failed to parse budget")
+    }
+
+    #[test]
+    fn test_budget_display() {
+        let budget =
+            parse_budget("scene = \"All scenes avg.\"\nmetric = \"fps\"\nmax_regression = 3");
+        assert_eq!(
+            budget.to_string(),
+            "All scenes avg.: FPS must not drop more than 3%"
+        );
+        let budget = Budget::new(SceneMetric::RenderTime, 20.0);
+        assert_eq!(
+            budget.to_string(),
+            "every scene: Render Time (ms) must not rise more than 20%"
+        );
+        let budget = parse_budget(
+            r#"
+name = "M0 widgets"
+tag = "cortex-m0"
+scene_regex = "Widgets.*"
+metric = "cpu"
+max_regression = 15
+"#,
+        );
+        assert_eq!(
+            budget.to_string(),
+            "M0 widgets: [cortex-m0] /Widgets.*/: CPU Usage (%) must not rise more than 15%"
+        );
+    }
+
+    #[test]
+    fn test_budget_applies_to() {
+        let m0 = create_config(Uuid::new_v4(), "Nucleo F091", vec!["cortex-m0"]);
+        let m7 = create_config(Uuid::new_v4(), "STM32F746 Discovery", vec!["cortex-m7"]);
+        let none = SceneAliases::default();
+
+        let budget = parse_budget(
+            "tag = \"cortex-m0\"\nscene = \"Containers*\"\nmetric = \"fps\"\nmax_regression = 1",
+        );
+        assert!(budget.applies_to(&m0, "Containers with opa", &none));
+        assert!(!budget.applies_to(&m0, "Widgets demo", &none));
+        assert!(!budget.applies_to(&m7, "Containers with opa", &none));
+
+        let budget = parse_budget(
+            "board = \"STM32F746 Discovery\"\nscene_regex = \"Multiple .*s\"\nmetric = \"fps\"\nmax_regression = 1",
+        );
+        assert!(budget.applies_to(&m7, "Multiple labels", &none));
+        assert!(!budget.applies_to(&m7, "Multiple labels, arc", &none));
+        assert!(!budget.applies_to(&m0, "Multiple labels", &none));
+    }
+
+    #[test]
+    fn test_budget_applies_to_aliased_scene() {
+        let m7 = create_config(Uuid::new_v4(), "STM32F746 Discovery", vec![]);
+        let aliases: SceneAliases = [(
+            "Containers with opa".to_string(),
+            "Containers, opacity".to_string(),
+        )]
+        .into_iter()
+        .collect();
+
+        // Patterns written for the old name, the normalized name or another case
+        let budget =
+            parse_budget("scene = \"Containers with*\"\nmetric = \"fps\"\nmax_regression = 1");
+        assert!(budget.applies_to(&m7, "Containers, opacity", &aliases));
+        assert!(!budget.applies_to(&m7, "Containers, opacity", &SceneAliases::default()));
+        let budget =
+            parse_budget("scene = \"containers opacity\"\nmetric = \"fps\"\nmax_regression = 1");
+        assert!(budget.applies_to(&m7, "Containers, opacity", &SceneAliases::default()));
+        let budget = parse_budget(
+            "scene_regex = \"all scenes avg\\\\.?\"\nmetric = \"fps\"\nmax_regression = 1",
+        );
+        assert!(budget.applies_to(&m7, "All scenes avg.", &SceneAliases::default()));
+    }
+
+    #[test]
+    fn test_find_budget_precedence() {
+        let board_a = create_config(Uuid::new_v4(), "Board A", vec!["cortex-m7"]);
+        let none = SceneAliases::default();
+        let find = |budgets: &[Budget]| {
+            find_budget(budgets, &board_a, "Widgets demo", &SceneMetric::FPS, &none)
+                .and_then(|budget| budget.name.clone())
+        };
+        let scene = parse_budget(
+            "name = \"scene\"\nscene = \"Widgets*\"\nmetric = \"fps\"\nmax_regression = 1",
+        );
+        let tag = parse_budget(
+            "name = \"tag\"\ntag = \"cortex-m7\"\nmetric = \"fps\"\nmax_regression = 1",
+        );
+        let board = parse_budget(
+            "name = \"board\"\nboard = \"Board A\"\nmetric = \"fps\"\nmax_regression = 1",
+        );
+        let mut other_board = board.clone();
+        other_board.name = Some("other board".to_string());
+
+        // A scene pattern wins over a board name and a board name over a tag, whatever the order
+        assert_eq!(
+            find(&[board.clone(), scene.clone()]).as_deref(),
+            Some("scene")
+        );
+        assert_eq!(
+            find(&[scene.clone(), board.clone()]).as_deref(),
+            Some("scene")
+        );
+        assert_eq!(
+            find(&[tag.clone(), scene.clone()]).as_deref(),
+            Some("scene")
+        );
+        assert_eq!(
+            find(&[board.clone(), tag.clone()]).as_deref(),
+            Some("board")
+        );
+        assert_eq!(
+            find(&[tag.clone(), board.clone()]).as_deref(),
+            Some("board")
+        );
+        // The first one defined wins among budgets with the same precedence
+        assert_eq!(
+            find(&[board.clone(), other_board.clone()]).as_deref(),
+            Some("board")
+        );
+        assert_eq!(find(&[other_board, board]).as_deref(), Some("other board"));
+    }
+
+    #[test]
+    fn test_invalid_budget_pattern() {
+        assert!(
+            toml::from_str::<Budget>("scene_regex = \"(\"\nmetric = \"fps\"\nmax_regression = 1")
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_evaluate_budgets() {
+        // FPS 60 -> 57 (-5%), render time 10 -> 13 (+30%)
+        let scenes = vec![
+            create_scene("All scenes avg.", 50.0, 57.0, 10.0, 13.0, 5.0),
+            create_scene("Widgets demo", 50.0, 60.0, 10.0, 11.0, 5.0),
+        ];
+        let delta = vec![
+            create_scene("All scenes avg.", 50.0, -3.0, 10.0, 3.0, 5.0),
+            create_scene("Widgets demo", 50.0, 0.0, 10.0, 1.0, 5.0),
+        ];
+        let budgets = vec![
+            parse_budget("scene = \"All scenes avg.\"\nmetric = \"fps\"\nmax_regression = 3"),
+            Budget::new(SceneMetric::RenderTime, 20.0),
+        ];
+
+        let violations = evaluate_budgets(
+            &create_results(scenes, delta),
+            &budgets,
+            &SignificanceConfig::default(),
+        );
+        assert_eq!(violations.len(), 2);
+        assert_eq!(violations[0].scene_name, "All scenes avg.");
+        assert_eq!(violations[0].metric, SceneMetric::FPS);
+        assert_eq!(violations[0].change, -5.0);
+        assert_eq!(violations[1].metric, SceneMetric::RenderTime);
+        assert_eq!(violations[1].change, 30.0);
+        assert_eq!(
+            violations[1].to_string(),
+            "Board A: 'All scenes avg.' Render Time (ms) changed by +30.0% (every scene: Render Time (ms) must not rise more than 20%)"
+        );
+    }
+
+    #[test]
+    fn test_evaluate_budgets_most_specific_rule() {
+        // Render time 10 -> 13 (+30%) on both scenes
+        let scenes = vec![
+            create_scene("Empty screen", 50.0, 60.0, 10.0, 13.0, 5.0),
+            create_scene("Widgets demo", 50.0, 60.0, 10.0, 13.0, 5.0),
+        ];
+        let delta = vec![
+            create_scene("Empty screen", 50.0, 0.0, 10.0, 3.0, 5.0),
+            create_scene("Widgets demo", 50.0, 0.0, 10.0, 3.0, 5.0),
+        ];
+        let budgets = vec![
+            Budget::new(SceneMetric::RenderTime, 20.0),
+            parse_budget(
+                "name = \"strict\"\nboard = \"Board A\"\nmetric = \"render_time\"\nmax_regression = 10",
+            ),
+            parse_budget(
+                "name = \"noisy widgets\"\nscene = \"Widgets*\"\nmetric = \"render_time\"\nmax_regression = 50",
+            ),
+        ];
+
+        let violations = evaluate_budgets(
+            &create_results(scenes, delta),
+            &budgets,
+            &SignificanceConfig::default(),
+        );
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].scene_name, "Empty screen");
+        assert_eq!(violations[0].budget.name.as_deref(), Some("strict"));
+    }
+
+    #[test]
+    fn test_evaluate_budgets_ignores_improvements_and_missing_baseline() {
+        let scenes = vec![create_scene("Empty screen", 50.0, 70.0, 10.0, 5.0, 5.0)];
+        let budgets = vec![Budget::new(SceneMetric::FPS, 1.0)];
+
+        let improved = create_results(
+            scenes.clone(),
+            vec![create_scene("Empty screen", 50.0, 10.0, 10.0, 0.0, 5.0)],
+        );
+        assert!(evaluate_budgets(&improved, &budgets, &SignificanceConfig::default()).is_empty());
+
+        let no_baseline = vec![BoardComparison::new(
+            create_config(Uuid::new_v4(), "Board A", vec![]),
+            BenchmarkRun::new(scenes),
+        )];
+        assert!(
+            evaluate_budgets(&no_baseline, &budgets, &SignificanceConfig::default()).is_empty()
+        );
+    }
+
+    #[test]
+    fn test_evaluate_budgets_ignores_insignificant_changes() {
+        let budgets = vec![Budget::new(SceneMetric::FPS, 1.0)];
+        let mut results = create_results(
+            vec![create_scene("Empty screen", 50.0, 57.0, 10.0, 5.0, 5.0)],
+            vec![create_scene("Empty screen", 50.0, -3.0, 10.0, 0.0, 5.0)],
+        );
+        results[0].run = BenchmarkRun::aggregate(&[results[0].run.clone(), results[0].run.clone()]);
+        results[0].run.aggregates[0].avg_fps.p_value = Some(0.3);
+        assert!(evaluate_budgets(&results, &budgets, &SignificanceConfig::default()).is_empty());
+
+        results[0].run.aggregates[0].avg_fps.p_value = Some(0.01);
+        assert_eq!(
+            evaluate_budgets(&results, &budgets, &SignificanceConfig::default()).len(),
+            1
+        );
+    }
+}
@@ -7,11 +7,14 @@ use plotters::{
 
 use crate::{
     prelude::*,
-    scene::{Scene, SceneMetric},
+    scene::{Scene, SceneAggregate, SceneMetric, format_value},
+    stats::MetricStats,
 };
 pub struct RunResult {
     run_name: String,
     scenes: Vec<Scene>,
+    /// Spread of the scenes over repetitions, drawn as min/max whiskers
+    aggregates: Vec<SceneAggregate>,
 }
 
 impl RunResult {
@@ -19,8 +22,27 @@ impl RunResult {
         Self {
             run_name: name.into(),
             scenes: results,
+            aggregates: Vec::new(),
         }
     }
+
+    pub fn with_aggregates(mut self, aggregates: Vec<SceneAggregate>) -> Self {
+        self.aggregates = aggregates;
+        self
+    }
+
+    fn get_scene(&self, scene_name: &str) -> Option<&Scene> {
+        self.scenes
+            .iter()
+            .find(|scene| scene.scene_name == scene_name)
+    }
+
+    fn get_stats(&self, scene_name: &str, metric: &SceneMetric) -> Option<&MetricStats> {
+        self.aggregates
+            .iter()
+            .find(|aggregate| aggregate.scene_name == scene_name)
+            .map(|aggregate| aggregate.get(metric))
+    }
 }
 pub static COLORS: [RGBColor; 9] = [
     RGBColor(220, 80, 80),   // Muted Red
@@ -34,16 +56,18 @@ pub static COLORS: [RGBColor; 9] = [
     RGBColor(180, 140, 210), // Lilac
 ];
 
-fn get_max_value(scenes: &Vec<Scene>, metric: &SceneMetric) -> i32 {
-    let mut max = 0;
+fn get_max_value(result: &RunResult, metric: &SceneMetric) -> f64 {
+    let mut max = 0.;
 
-    for scene in scenes {
-        let value = scene.get_value(&metric);
+    for scene in &result.scenes {
+        let value = result
+            .get_stats(&scene.scene_name, metric)
+            .map_or(scene.get_value(metric), |stats| stats.max);
         if value > max {
             max = value;
         }
     }
-    return max;
+    max
 }
 
 pub fn create_comparison_chart(
@@ -57,14 +81,21 @@ pub fn create_comparison_chart(
         return Err(Error::ResultSliceEmpty);
     }
 
-    let mut max_value = 0;
+    let mut max_value = 0.;
     for result in results {
-        let value = get_max_value(&result.scenes, &metric);
+        let value = get_max_value(result, metric);
         if value > max_value {
             max_value = value;
         }
     }
-    let scene_count = results[0].scenes.len();
+    // Every scene of every run, in the order they first appear, as runs may miss some scenes
+    let mut scene_names: Vec<&str> = Vec::new();
+    for scene in results.iter().flat_map(|result| &result.scenes) {
+        if !scene_names.contains(&scene.scene_name.as_str()) {
+            scene_names.push(&scene.scene_name);
+        }
+    }
+    let scene_count = scene_names.len();
     let bar_width = 2.;
     let bar_gap = 0.;
     let scene_gap = 1.;
@@ -73,9 +104,9 @@ pub fn create_comparison_chart(
     let total_width_per_scene = total_scene_width + scene_gap;
 
     let run_names: Vec<&str> = results.iter().map(|r| r.run_name.as_str()).collect();
-    let mut chart = ChartBuilder::on(&root)
+    let mut chart = ChartBuilder::on(root)
         .caption(
-            &format!("{} - {} [{}]", title, metric.label(), run_names.join(", ")),
+            format!("{} - {} [{}]", title, metric.label(), run_names.join(", ")),
             ("sans-serif", 20),
         )
         .margin(10)
@@ -97,39 +128,57 @@ pub fn create_comparison_chart(
         .draw()?;
 
     for (result_i, (result, color)) in results.iter().zip(colors).enumerate() {
-        let values: Vec<i32> = result
-            .scenes
+        // Position of every scene of the run in the chart, with its value
+        let values: Vec<(usize, &Scene, f64)> = scene_names
             .iter()
-            .map(|scene| scene.get_value(&metric))
+            .enumerate()
+            .filter_map(|(i, name)| {
+                let scene = result.get_scene(name)?;
+                Some((i, scene, scene.get_value(metric)))
+            })
             .collect();
+        let bar_y = |i: usize| {
+            let scene_start_y = i as f32 * total_width_per_scene;
+            scene_start_y + result_i as f32 * (bar_width + bar_gap)
+        };
 
         chart
-            .draw_series(values.iter().enumerate().map(|(i, &value)| {
-                let scene_start_y = i as f32 * total_width_per_scene;
-                let bar_offset = result_i as f32 * (bar_width + bar_gap);
-                let y0 = scene_start_y + bar_offset;
+            .draw_series(values.iter().map(|&(i, _, value)| {
+                let y0 = bar_y(i);
                 let y1 = y0 + bar_width;
                 Rectangle::new([(0.0, y0), (value as f32, y1)], color.filled())
             }))?
             .label(result.run_name.clone())
             .legend(|(x, y)| PathElement::new(vec![(x, y), (x + 10, y)], *color));
 
-        chart.draw_series(values.iter().enumerate().map(|(i, scene)| {
-            let scene_start_y = i as f32 * total_width_per_scene;
-            let bar_offset = result_i as f32 * (bar_width + bar_gap);
-            let y0 = scene_start_y + bar_offset + bar_width;
-            Text::new(scene.to_string(), (0.0, y0), ("sans-serif", 12))
+        let whisker_color = RGBColor(60, 60, 60);
+        for &(i, scene, _) in &values {
+            let Some(stats) = result.get_stats(&scene.scene_name, metric) else {
+                continue;
+            };
+            let y = bar_y(i) + bar_width / 2.0;
+            let (min, max) = (stats.min as f32, stats.max as f32);
+            chart.draw_series([
+                PathElement::new(vec![(min, y), (max, y)], whisker_color),
+                PathElement::new(vec![(min, y - 0.5), (min, y + 0.5)], whisker_color),
+                PathElement::new(vec![(max, y - 0.5), (max, y + 0.5)], whisker_color),
+            ])?;
+        }
+
+        chart.draw_series(values.iter().map(|&(i, _, value)| {
+            let y0 = bar_y(i) + bar_width;
+            Text::new(format_value(value), (0.0, y0), ("sans-serif", 12))
         }))?;
     }
-    chart.draw_series(results[0].scenes.iter().enumerate().map(|(i, scene)| {
+    chart.draw_series(scene_names.iter().enumerate().map(|(i, name)| {
         let scene_center_y = i as f32 * total_width_per_scene + total_scene_width / 2.0;
         let max_bar_height = results
             .iter()
-            .map(|r| r.scenes[i].get_value(&metric))
-            .max()
-            .unwrap_or(0) as f32;
+            .filter_map(|result| result.get_scene(name))
+            .map(|scene| scene.get_value(metric))
+            .fold(0., f64::max) as f32;
         Text::new(
-            scene.scene_name.clone(),
+            name.to_string(),
             (max_bar_height + 10.0, scene_center_y),
             ("sans-serif", 10),
         )
@@ -138,3 +187,36 @@ pub fn create_comparison_chart(
     chart.configure_series_labels().draw()?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::result::fixtures::create_scene;
+    use plotters::prelude::IntoDrawingArea;
+
+    #[test]
+    fn test_comparison_chart_different_scenes() {
+        let results = vec![
+            RunResult::new(
+                "a",
+                vec![create_scene("Empty screen", 30.0, 60.0, 4.0, 0.0, 4.0)],
+            ),
+            RunResult::new(
+                "b",
+                vec![
+                    create_scene("Widgets demo", 30.0, 20.0, 4.0, 0.0, 4.0),
+                    create_scene("Empty screen", 30.0, 58.0, 4.0, 0.0, 4.0),
+                ],
+            ),
+        ];
+        let mut svg = String::new();
+        {
+            let root = SVGBackend::with_string(&mut svg, (400, 300)).into_drawing_area();
+            create_comparison_chart(&root, "Test", &results, &SceneMetric::FPS, &COLORS)
+                .expect("Failed to draw chart");
+            root.present().expect("Failed to render chart");
+        }
+        assert!(svg.contains("Widgets demo"));
+        assert!(svg.contains("Empty screen"));
+    }
+}
@@ -0,0 +1,242 @@
+//! GitHub Check Run built from the exported results, see `publish-check`.
+
+use crate::export::ResultExport;
+use crate::scene::{format_delta, format_percentage};
+
+/// File the annotations are attached to, they aren't tied to a line of the sources.
+pub const ANNOTATION_PATH: &str = ".github";
+
+/// Maximum length of a check run summary accepted by GitHub.
+const MAX_SUMMARY_LEN: usize = 65535;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckConclusion {
+    /// No budget was exceeded
+    Success,
+    /// At least one budget was exceeded
+    Failure,
+    /// There was no baseline to compare against
+    Neutral,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnnotationLevel {
+    Warning,
+    Failure,
+}
+
+/// A regressed scene metric.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CheckAnnotation {
+    pub level: AnnotationLevel,
+    pub title: String,
+    pub message: String,
+}
+
+/// Everything published in a check run.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CheckReport {
+    pub conclusion: CheckConclusion,
+    pub title: String,
+    /// The Markdown report, truncated to the length GitHub accepts
+    pub summary: String,
+    pub annotations: Vec<CheckAnnotation>,
+}
+
+impl CheckReport {
+    /// Builds the check run of `export`, with `report` (the PR comment) as its summary.
+    ///
+    /// Exceeded budgets are failure annotations and other regressions warning annotations.
+    pub fn new(export: &ResultExport, report: &str) -> Self {
+        let regressions = export.regressions();
+        let (conclusion, title) = if !export.violations.is_empty() {
+            (
+                CheckConclusion::Failure,
+                format!(
+                    "{} performance budget violation(s)",
+                    export.violations.len()
+                ),
+            )
+        } else if export.baseline.is_none() {
+            (
+                CheckConclusion::Neutral,
+                "No baseline results to compare against".to_string(),
+            )
+        } else if regressions.is_empty() {
+            (CheckConclusion::Success, "No regressions".to_string())
+        } else {
+            (
+                CheckConclusion::Success,
+                format!("{} regression(s) within budget", regressions.len()),
+            )
+        };
+
+        let mut annotations: Vec<CheckAnnotation> = export
+            .violations
+            .iter()
+            .map(|violation| CheckAnnotation {
+                level: AnnotationLevel::Failure,
+                title: format!("{}: {}", violation.board_config.name, violation.scene_name),
+                message: format!(
+                    "{} changed by {} ({})",
+                    violation.metric.label(),
+                    format_percentage(violation.change),
+                    violation.budget
+                ),
+            })
+            .collect();
+        for (board_config, change) in regressions {
+            if export.exceeds_budget(board_config, change) {
+                continue;
+            }
+            let mut message = format!(
+                "{} changed by {}",
+                change.metric.label(),
+                format_delta(change.delta)
+            );
+            if let Some(relative_delta) = change.relative_delta {
+                message += &format!(" ({})", format_percentage(relative_delta));
+            }
+            annotations.push(CheckAnnotation {
+                level: AnnotationLevel::Warning,
+                title: format!("{}: {}", board_config.name, change.scene_name),
+                message,
+            });
+        }
+
+        Self {
+            conclusion,
+            title,
+            summary: truncate(report, MAX_SUMMARY_LEN),
+            annotations,
+        }
+    }
+}
+
+/// Truncates `text` to at most `max_len` bytes, on a character boundary.
+fn truncate(text: &str, max_len: usize) -> String {
+    const ELLIPSIS: &str = "\n\n…";
+    if text.len() <= max_len {
+        return text.to_string();
+    }
+    let mut end = max_len - ELLIPSIS.len();
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}{}", &text[..end], ELLIPSIS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::budget::{Budget, BudgetCheck, check_budgets};
+    use crate::config::{MetricsConfig, SignificanceConfig, ThresholdConfig};
+    use crate::matching::BoardMatch;
+    use crate::result::BoardComparison;
+    use crate::result::fixtures::{
+        create_baseline, create_config, create_regressed_results, create_scene,
+    };
+    use crate::scene::{BenchmarkRun, SceneAliases, SceneMetric};
+    use uuid::Uuid;
+
+    fn create_export(budgets: &[Budget], with_baseline: bool) -> ResultExport {
+        let results = create_regressed_results();
+        let violations: Vec<_> = check_budgets(
+            &results,
+            budgets,
+            &SceneAliases::default(),
+            &SignificanceConfig::default(),
+        )
+        .iter()
+        .filter_map(BudgetCheck::violation)
+        .collect();
+        let baseline = create_baseline();
+        ResultExport::new(
+            &results,
+            &violations,
+            with_baseline.then_some(&baseline),
+            &SceneMetric::PARSED,
+            &ThresholdConfig::default(),
+            &SignificanceConfig::default(),
+        )
+    }
+
+    #[test]
+    fn test_check_report() {
+        let export = create_export(&[Budget::new(SceneMetric::FPS, 3.0)], true);
+        let check = CheckReport::new(&export, "Report");
+        assert_eq!(check.conclusion, CheckConclusion::Failure);
+        assert_eq!(check.title, "1 performance budget violation(s)");
+        assert_eq!(check.summary, "Report");
+        assert_eq!(
+            check.annotations,
+            vec![
+                CheckAnnotation {
+                    level: AnnotationLevel::Failure,
+                    title: "Board A: Empty screen".to_string(),
+                    message: "FPS changed by -5.0% (every scene: FPS must not drop more than 3%)"
+                        .to_string(),
+                },
+                CheckAnnotation {
+                    level: AnnotationLevel::Warning,
+                    title: "Board A: Widgets demo".to_string(),
+                    message: "Render Time (ms) changed by +10 (+100.0%)".to_string(),
+                },
+            ]
+        );
+
+        let check = CheckReport::new(&create_export(&[], true), "Report");
+        assert_eq!(check.conclusion, CheckConclusion::Success);
+        assert_eq!(check.title, "2 regression(s) within budget");
+        assert!(
+            check
+                .annotations
+                .iter()
+                .all(|annotation| annotation.level == AnnotationLevel::Warning)
+        );
+
+        let check = CheckReport::new(&create_export(&[], false), "Report");
+        assert_eq!(check.conclusion, CheckConclusion::Neutral);
+    }
+
+    #[test]
+    fn test_check_report_derived_metrics() {
+        let config = create_config(Uuid::new_v4(), "Board A", vec![]);
+        let metrics = MetricsConfig::default();
+        let mut baseline_run =
+            BenchmarkRun::new(vec![create_scene("Empty screen", 0.0, 30.0, 0.0, 5.0, 0.0)]);
+        let mut run =
+            BenchmarkRun::new(vec![create_scene("Empty screen", 0.0, 27.0, 0.0, 5.0, 0.0)]);
+        metrics.apply(&mut run).unwrap();
+        metrics.apply(&mut baseline_run).unwrap();
+        let comparison = BoardComparison::compared(
+            config,
+            run,
+            &baseline_run,
+            BoardMatch::Id,
+            &SceneAliases::default(),
+        );
+        let baseline = create_baseline();
+        let export = ResultExport::new(
+            &[comparison],
+            &[],
+            Some(&baseline),
+            &metrics.classified(&[]),
+            &ThresholdConfig::default(),
+            &SignificanceConfig::default(),
+        );
+
+        // The score drops with the FPS, but isn't counted as a second regression
+        let check = CheckReport::new(&export, "Report");
+        assert_eq!(check.title, "1 regression(s) within budget");
+        assert_eq!(check.annotations.len(), 1);
+    }
+
+    #[test]
+    fn test_truncate() {
+        assert_eq!(truncate("short", 10), "short");
+        let truncated = truncate(&"é".repeat(20), 20);
+        assert!(truncated.len() <= 20);
+        assert!(truncated.ends_with('…'));
+    }
+}
@@ -12,18 +12,44 @@ use crate::scene::SceneMetric;
 #[command(name = "ejlv_cli")]
 #[command(about = "EJ LVGL CLI - Job handler for the LVGL's EJ workspace")]
 pub struct Cli {
+    /// Path to the project configuration file [default: ejlv.toml]
+    ///
+    /// Values in the configuration file are used as defaults for the command line flags.
+    #[arg(long, global = true, env = "EJLV_CONFIG")]
+    pub config: Option<PathBuf>,
+
+    #[command(flatten)]
+    pub gh: GhArgs,
+
     #[command(subcommand)]
     pub command: Commands,
 }
 
+/// GitHub repository the results are compared against and published to.
+#[derive(Args)]
+pub struct GhArgs {
+    /// GitHub repository owner [default: lvgl]
+    #[arg(long, global = true, env = "EJLV_GH_OWNER")]
+    pub owner: Option<String>,
+
+    /// GitHub repository name [default: lvgl]
+    #[arg(long, global = true, env = "EJLV_GH_REPO")]
+    pub repo: Option<String>,
+
+    /// Branch whose latest commit is used as the baseline for comparisons [default: master]
+    #[arg(long, global = true, env = "EJLV_BASELINE_BRANCH")]
+    pub baseline_branch: Option<String>,
+}
+
 /// Available commands for the EJ CLI testing and setup tool.
 #[derive(Subcommand)]
+#[allow(clippy::large_enum_variant)]
 pub enum Commands {
     /// Dispatch a test build job
     DispatchBuild {
         /// Path to the EJD's unix socket
         #[arg(short, long)]
-        socket: PathBuf,
+        socket: Option<PathBuf>,
         #[command(flatten)]
         job: DispatchArgs,
     },
@@ -32,11 +58,59 @@ pub enum Commands {
     DispatchRun {
         /// Path to the EJD's unix socket
         #[arg(short, long)]
-        socket: PathBuf,
+        socket: Option<PathBuf>,
 
         /// Path to the output comment (.md)
         #[arg(long)]
-        comment_path: PathBuf,
+        comment_path: Option<PathBuf>,
+
+        /// Path to a JSON export of the compared results (boards, scenes, baseline and changes)
+        #[arg(long)]
+        json_out: Option<PathBuf>,
+
+        /// Path to a CSV export of the compared results, one row per board, scene and metric
+        #[arg(long)]
+        csv_out: Option<PathBuf>,
+
+        /// Path to a JUnit XML report (.xml) with a test suite per board and a test case per
+        /// budget check
+        #[arg(long)]
+        junit_out: Option<PathBuf>,
+
+        /// Exit with a distinct code (3) when a configured budget is exceeded
+        #[arg(long)]
+        fail_on_regression: bool,
+
+        /// Path to a budgets file (.toml) with `[[budgets]]` rules per board, tag and scene
+        #[arg(long)]
+        budgets: Option<PathBuf>,
+
+        /// Number of times the run is dispatched, results are aggregated over the repetitions
+        /// and compared against as many baseline results [default: 1]
+        #[arg(long)]
+        repetitions: Option<usize>,
+
+        /// Commit compared against, instead of the merge base of the PR or the latest commit
+        /// of the baseline branch. Older first-parent commits are used if it has no result
+        #[arg(long)]
+        baseline_commit: Option<String>,
+
+        /// Number of first-parent commits with results combined into the baseline [default: 1]
+        #[arg(long)]
+        baseline_window: Option<usize>,
+
+        /// Path to a board mapping file (.toml) pairing renamed board configs with their baseline
+        #[arg(long)]
+        board_mapping: Option<PathBuf>,
+
+        /// PR number associated with this run, its merge base is used as the baseline
+        #[arg(long)]
+        pr_number: Option<u64>,
+
+        /// Github token used to fetch the baseline commits, unauthenticated requests are
+        /// rate limited
+        #[arg(long, env = "GITHUB_TOKEN")]
+        gh_token: Option<String>,
 
         #[command(flatten)]
         job: DispatchArgs,
@@ -46,7 +120,7 @@ pub enum Commands {
     CommentPR {
         /// Path to the output comment (.md)
         #[arg(long)]
-        comment_path: PathBuf,
+        comment_path: Option<PathBuf>,
 
         /// PR number associated with this run
         #[arg(long)]
@@ -58,13 +132,42 @@ pub enum Commands {
 
         /// A comment (hidden) signature
         #[arg(long)]
-        signature: String,
+        signature: Option<String>,
+    },
+
+    /// Publish the report as a GitHub Check Run on the PR head commit
+    PublishCheck {
+        /// Path to the report (.md) used as the check run summary
+        #[arg(long)]
+        comment_path: Option<PathBuf>,
+
+        /// Results exported with `--json-out`, defaults to `output.json_out`
+        #[arg(long)]
+        results: Option<PathBuf>,
+
+        /// PR number associated with this run
+        #[arg(long)]
+        pr_number: u64,
+
+        /// Commit the check run is attached to, defaults to the head of the PR
+        #[arg(long)]
+        head_sha: Option<String>,
+
+        /// Github token with `checks: write` permission
+        #[arg(long)]
+        gh_token: String,
+
+        /// Name of the check run, GitHub shows the latest check run with a given name
+        #[arg(long, default_value = "Performance benchmarks")]
+        name: String,
     },
 
     /// Generate Benchmark Results Graph
     BenchmarkGraph {
         /// Path to a folder containing multiple files with the benchmark results
-        /// Each file shall contain only one benchmark result
+        /// Each `Benchmark Summary` block found in a file is drawn as a separate run
+        /// The files of a sub-directory are repetitions of the same run, drawn as their mean
+        /// with min/max whiskers
         /// The parser expects to find `Benchmark Summary`
         /// Previous lines are ignored so it's safe to pass the full output of a benchmark
         /// even if stuff was logged before the results
@@ -75,7 +178,8 @@ pub enum Commands {
         #[arg(short, long)]
         output: PathBuf,
 
-        /// The metric you're interested in
+        /// The metric you're interested in: fps, cpu, avg_time, render_time, flush_time or one of
+        /// the derived render_share, frame_headroom and score
         #[arg(short, long)]
         metric: SceneMetric,
 
@@ -85,6 +189,19 @@ pub enum Commands {
         #[arg(long)]
         v_res: u32,
     },
+
+    /// Inspect the project configuration
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommands,
+    },
+}
+
+/// Configuration related commands.
+#[derive(Subcommand)]
+pub enum ConfigCommands {
+    /// Print the configuration after merging the configuration file with the command line
+    Show,
 }
 
 /// Arguments for dispatching a job.
@@ -92,7 +209,7 @@ pub enum Commands {
 pub struct DispatchArgs {
     /// The maximum job duration in seconds
     #[arg(long)]
-    pub seconds: u64,
+    pub seconds: Option<u64>,
 
     /// Git commit hash
     #[arg(long)]
@@ -100,7 +217,7 @@ pub struct DispatchArgs {
 
     /// Git remote url
     #[arg(long)]
-    pub remote_url: String,
+    pub remote_url: Option<String>,
 
     /// Optional git remote token
     #[arg(long)]
@@ -1,53 +1,286 @@
 use ej_config::ej_board_config::EjBoardConfigApi;
 use tracing::warn;
 
-use crate::scene::Scene;
+use crate::budget::BudgetViolation;
+use crate::config::{MetricsConfig, SignificanceConfig, ThresholdConfig};
+use crate::gh::Baseline;
+use crate::result::{
+    BoardComparison, BoardOutcome, Change, SceneComparison, classify_delta, relative_delta,
+};
+use crate::scene::{
+    SceneAggregate, SceneMetric, WEIGHTED_SCORE_SCENE, format_delta, format_percentage,
+    format_value, is_zero_delta,
+};
+use crate::stats::MetricStats;
 
-fn format_cell(value: i32, delta: i32) -> String {
-    if delta == 0 {
-        format!(" {} |", value)
+fn change_icon(change: Change) -> &'static str {
+    match change {
+        Change::Improvement => ":green_circle:",
+        Change::Regression => ":red_circle:",
+        Change::Neutral => "",
+    }
+}
+
+/// Formats a value, with its standard deviation over repetitions if any.
+fn format_mean(value: f64, stats: Option<&MetricStats>) -> String {
+    match stats {
+        Some(stats) => format!("{} ± {}", format_value(value), format_value(stats.std_dev)),
+        None => format_value(value),
+    }
+}
+
+/// Formats the p-value of a change.
+fn format_p_value(p_value: f64) -> String {
+    if p_value < 0.001 {
+        "p<0.001".to_string()
     } else {
-        format!(" {} ({:+}) |", value, delta)
+        format!("p={:.3}", p_value)
+    }
+}
+
+/// Formats a value and its change, marked as an improvement or a regression.
+///
+/// Changes below `threshold` percent or that aren't statistically significant are neutral and
+/// shown in italics.
+fn format_cell(
+    metric: &SceneMetric,
+    value: f64,
+    delta: f64,
+    threshold: f64,
+    stats: Option<&MetricStats>,
+    significance: &SignificanceConfig,
+) -> String {
+    let value_str = format_mean(value, stats);
+    if is_zero_delta(delta) {
+        return format!(" {} |", value_str);
+    }
+    let p_value = stats.and_then(|stats| stats.p_value);
+    let mut change = vec![format_delta(delta)];
+    change.extend(relative_delta(value, delta).map(format_percentage));
+    change.extend(p_value.map(format_p_value));
+    let change = format!("({})", change.join(", "));
+    let significant = significance.is_significant(p_value);
+    match classify_delta(metric, value, delta, threshold, significant) {
+        Change::Neutral => format!(" {} _{}_ |", value_str, change),
+        classification => format!(
+            " {} {} {} |",
+            value_str,
+            change,
+            change_icon(classification)
+        ),
     }
 }
-fn format_table(results: &Vec<Scene>, delta: &Vec<Scene>) -> String {
+/// Formats the name of a scene, marked when it was added or removed since the baseline.
+fn format_scene_name(scene: &SceneComparison) -> String {
+    match scene {
+        SceneComparison::Added(_) => format!("{} _(new)_", scene.scene_name()),
+        SceneComparison::Removed(_) => format!("~~{}~~ _(removed)_", scene.scene_name()),
+        SceneComparison::Compared { .. } | SceneComparison::NoBaseline(_) => {
+            scene.scene_name().to_string()
+        }
+    }
+}
+
+/// Formats the scenes with a column per metric of `columns`.
+fn format_table(
+    scenes: &[SceneComparison],
+    aggregates: &[SceneAggregate],
+    columns: &[SceneMetric],
+    thresholds: &ThresholdConfig,
+    significance: &SignificanceConfig,
+) -> String {
     let mut table = String::new();
-    table += "| Scene Name | Avg CPU (%) | Avg FPS | Avg Time (ms) | Render Time (ms) | Flush Time (ms) |\n";
-    table += "|------------|-------------|---------|---------------|------------------|-----------------|\n";
-
-    for (new_result, delta_result) in results.iter().zip(delta) {
-        table += &format!("| {} |", new_result.scene_name);
-        table += &format_cell(new_result.avg_cpu, delta_result.avg_cpu);
-        table += &format_cell(new_result.avg_fps, delta_result.avg_fps);
-        table += &format_cell(new_result.avg_time, delta_result.avg_time);
-        table += &format_cell(new_result.render_time, delta_result.render_time);
-        table += &format_cell(new_result.flush_time, delta_result.flush_time);
+    table += "| Scene Name |";
+    for metric in columns {
+        table += &format!(" {} |", metric.label());
+    }
+    table += "\n";
+    table += "|------------|";
+    for metric in columns {
+        table += &format!("{}|", "-".repeat(metric.label().chars().count() + 2));
+    }
+    table += "\n";
+
+    for scene in scenes {
+        table += &format!("| {} |", format_scene_name(scene));
+        if let SceneComparison::Removed(_) = scene {
+            table += &" - |".repeat(columns.len());
+            table += "\n";
+            continue;
+        }
+        let aggregate = aggregates
+            .iter()
+            .find(|aggregate| aggregate.scene_name == scene.scene_name());
+        for metric in columns {
+            table += &format_cell(
+                metric,
+                scene.scene().get_value(metric),
+                scene.delta().map_or(0.0, |delta| delta.get_value(metric)),
+                thresholds.get(metric),
+                aggregate.map(|aggregate| aggregate.get(metric)),
+                significance,
+            );
+        }
         table += "\n";
     }
     table
 }
-pub fn generate_comment(results: &Vec<(EjBoardConfigApi, Vec<Scene>, Vec<Scene>)>) -> String {
+/// Lists the mean, median, standard deviation, min and max of every scene metric of `columns`.
+fn format_spread_table(aggregates: &[SceneAggregate], columns: &[SceneMetric]) -> String {
+    let mut table = String::new();
+    table += "| Scene Name | Metric | Mean | Median | Std Dev | Min | Max |\n";
+    table += "|------------|--------|------|--------|---------|-----|-----|\n";
+    for aggregate in aggregates {
+        for metric in columns {
+            let stats = aggregate.get(metric);
+            table += &format!(
+                "| {} | {} | {} | {} | {} | {} | {} |\n",
+                aggregate.scene_name,
+                metric.label(),
+                format_value(stats.mean),
+                format_value(stats.median),
+                format_value(stats.std_dev),
+                format_value(stats.min),
+                format_value(stats.max)
+            );
+        }
+    }
+    table
+}
+fn outcome_icon(outcome: &BoardOutcome) -> &'static str {
+    match outcome {
+        BoardOutcome::Ok(_) => ":white_check_mark:",
+        BoardOutcome::Skipped => ":fast_forward:",
+        BoardOutcome::ParseFailed(_) | BoardOutcome::RunFailed(_) => ":x:",
+        BoardOutcome::TimedOut => ":hourglass:",
+    }
+}
+
+/// Lists the outcome of every board, only when at least one of them didn't succeed.
+fn format_board_status(outcomes: &[(EjBoardConfigApi, BoardOutcome)]) -> String {
+    if outcomes.iter().all(|(_, outcome)| outcome.is_ok()) {
+        return String::new();
+    }
+    let mut status = String::new();
+    status += "### Board status\n\n";
+    status += "| Board | Status |\n";
+    status += "|-------|--------|\n";
+    for (board_config, outcome) in outcomes {
+        status += &format!(
+            "| {} | {} {} |\n",
+            board_config.name,
+            outcome_icon(outcome),
+            outcome
+        );
+    }
+    status += "\n";
+    status
+}
+
+fn format_budget_violations(violations: &[BudgetViolation]) -> String {
+    if violations.is_empty() {
+        return String::new();
+    }
+    let mut section = String::new();
+    section += "### :rotating_light: Performance budget exceeded\n\n";
+    section += "| Board | Scene | Metric | Change | Rule |\n";
+    section += "|-------|-------|--------|--------|------|\n";
+    for violation in violations {
+        section += &format!(
+            "| {} | {} | {} | {} | {} |\n",
+            violation.board_config.name,
+            violation.scene_name,
+            violation.metric.label(),
+            format_percentage(violation.change),
+            violation.budget
+        );
+    }
+    section += "\n";
+    section
+}
+
+pub fn generate_comment(
+    results: &[BoardComparison],
+    outcomes: &[(EjBoardConfigApi, BoardOutcome)],
+    violations: &[BudgetViolation],
+    baseline: Option<&Baseline>,
+    thresholds: &ThresholdConfig,
+    significance: &SignificanceConfig,
+    metrics: &MetricsConfig,
+) -> String {
+    let columns = metrics.columns();
     let mut comment = String::new();
     comment += "Hi :wave:, thank you for your PR!\n\n";
     comment += "We've run some performance benchmarks. Here are the results:\n\n";
+    if let Some(baseline) = baseline {
+        comment += &format!("_Compared against {}_\n\n", baseline);
+    }
+    comment += &format_board_status(outcomes);
+    comment += &format_budget_violations(violations);
 
-    for (board_config, new_result, delta) in results {
-        let all_scene_avg = new_result
-            .iter()
-            .rfind(|scene| scene.scene_name == "All scenes avg.");
-        let delta_all_scene_avg = delta
+    for (i, comparison) in results.iter().enumerate() {
+        let (board_config, run) = (&comparison.board_config, &comparison.run);
+        let all_scene_avg = comparison
+            .find_scene("All scenes avg.")
+            .filter(|scene| !matches!(scene, SceneComparison::Removed(_)));
+        let weighted_score = comparison
+            .find_scene(WEIGHTED_SCORE_SCENE)
+            .filter(|scene| !matches!(scene, SceneComparison::Removed(_)));
+        let is_first_board_run = i == 0 || results[i - 1].board_config != *board_config;
+        if is_first_board_run {
+            comment += &format!("#### {}\n\n", board_config);
+            match comparison.baseline_match {
+                Some(baseline_match) => {
+                    comment += &format!("_Baseline matched by {}_\n\n", baseline_match)
+                }
+                None => comment += "_No baseline results for this board_\n\n",
+            }
+        }
+        let board_run_count = results
             .iter()
-            .rfind(|scene| scene.scene_name == "All scenes avg.");
-        comment += &format!("#### {}\n\n", board_config);
-
-        let hide_full_table_in_details = match (all_scene_avg, delta_all_scene_avg) {
-            (Some(all_scene_avg), Some(delta_all_scene_avg)) => {
-                let all_scene_avg = vec![all_scene_avg.clone()];
-                let delta_all_scene_avg = vec![delta_all_scene_avg.clone()];
-                comment += &format_table(&all_scene_avg, &delta_all_scene_avg);
+            .filter(|comparison| comparison.board_config == *board_config)
+            .count();
+        if board_run_count > 1 {
+            let title = run
+                .title()
+                .or_else(|| run.version.clone())
+                .unwrap_or_else(|| "Benchmark".to_string());
+            comment += &format!("##### {}\n\n", title);
+        }
+        if let Some(summary) = run.metadata_summary() {
+            comment += &format!("{}\n\n", summary);
+        }
+        if !run.aggregates.is_empty() {
+            comment += &format!(
+                "_Mean ± standard deviation of {} repetitions_\n\n",
+                run.repetitions()
+            );
+        }
+        if !comparison.metadata_mismatches.is_empty() {
+            comment += "> [!WARNING]\n";
+            comment += "> The baseline was run with different settings:\n";
+            for mismatch in &comparison.metadata_mismatches {
+                comment += &format!("> - {}\n", mismatch);
+            }
+            comment += "\n";
+        }
+
+        let hide_full_table_in_details = match all_scene_avg {
+            Some(all_scene_avg) => {
+                let summary: Vec<SceneComparison> = std::iter::once(all_scene_avg)
+                    .chain(weighted_score)
+                    .cloned()
+                    .collect();
+                comment += &format_table(
+                    &summary,
+                    &run.aggregates,
+                    &columns,
+                    thresholds,
+                    significance,
+                );
                 true
             }
-            (_, _) => {
+            None => {
                 warn!("Coulnd't find 'All scenes avg.' entry for {}", board_config);
                 false
             }
@@ -59,10 +292,40 @@ pub fn generate_comment(results: &Vec<(EjBoardConfigApi, Vec<Scene>, Vec<Scene>)
             comment += "\nDetailed Results Per Scene";
             comment += "\n</summary>\n\n";
         }
-        comment += &format_table(new_result, delta);
+        comment += &format_table(
+            &comparison.scenes,
+            &run.aggregates,
+            &columns,
+            thresholds,
+            significance,
+        );
         if hide_full_table_in_details {
             comment += "\n\n</details>\n\n";
         }
+        if !run.aggregates.is_empty() {
+            comment += "\n<details>";
+            comment += "\n<summary>";
+            comment += "\nSpread Over Repetitions";
+            comment += "\n</summary>\n\n";
+            comment += &format_spread_table(&run.aggregates, &columns);
+            comment += "\n\n</details>\n\n";
+        }
+    }
+
+    for (board_config, outcome) in outcomes {
+        match outcome {
+            BoardOutcome::ParseFailed(diagnostic) => {
+                comment += &format!("#### {}\n\n", board_config);
+                comment += ":x: Failed to parse the benchmark results:\n\n";
+                comment += &format!("```text\n{}\n```\n\n", diagnostic);
+            }
+            BoardOutcome::RunFailed(logs) if !logs.is_empty() => {
+                comment += &format!("#### {}\n\n", board_config);
+                comment += ":x: The run failed. Last lines of the logs:\n\n";
+                comment += &format!("```text\n{}\n```\n\n", logs);
+            }
+            _ => {}
+        }
     }
 
     comment += "\n\n---\n\n";
@@ -73,146 +336,199 @@ pub fn generate_comment(results: &Vec<(EjBoardConfigApi, Vec<Scene>, Vec<Scene>)
 #[cfg(test)]
 mod tests {
     use super::*;
-    use uuid::Uuid;
-
-    // Helper function to create a test config
-    fn create_config(name: &str, tags: Vec<&str>) -> EjBoardConfigApi {
-        EjBoardConfigApi {
-            id: Uuid::parse_str("550e8400-e29b-41d4-a716-446655440000").unwrap(),
-            name: name.to_string(),
-            tags: tags.into_iter().map(|s| s.to_string()).collect(),
+    use crate::budget::{Budget, SceneGlob};
+    use crate::matching::BoardMatch;
+    use crate::result::fixtures::{
+        BOARD_ID, compare_scenes, create_baseline, create_config, create_scene,
+    };
+    use crate::scene::{BenchmarkRun, Scene};
+
+    fn create_comparison(
+        board_config: EjBoardConfigApi,
+        run: BenchmarkRun,
+        deltas: Vec<Scene>,
+    ) -> BoardComparison {
+        BoardComparison {
+            board_config,
+            scenes: compare_scenes(run.scenes.clone(), deltas),
+            run,
+            baseline_match: Some(BoardMatch::Id),
+            metadata_mismatches: Vec::new(),
         }
     }
 
     #[test]
     fn test_format_cell() {
-        assert_eq!(format_cell(65, 5), " 65 (+5) |");
-        assert_eq!(format_cell(19, -3), " 19 (-3) |");
-        assert_eq!(format_cell(0, 0), " 0 |");
+        use SceneMetric::*;
+        let significance = SignificanceConfig::default();
+
+        assert_eq!(
+            format_cell(&CPU, 65.0, 5.0, 0.0, None, &significance),
+            " 65 (+5, +8.3%) :red_circle: |"
+        );
+        assert_eq!(
+            format_cell(&FPS, 19.0, -3.0, 0.0, None, &significance),
+            " 19 (-3, -13.6%) :red_circle: |"
+        );
+        assert_eq!(
+            format_cell(&FPS, 22.0, 3.0, 0.0, None, &significance),
+            " 22 (+3, +15.8%) :green_circle: |"
+        );
+        assert_eq!(
+            format_cell(&CPU, 0.0, 0.0, 0.0, None, &significance),
+            " 0 |"
+        );
+        assert_eq!(
+            format_cell(&RenderTime, 3.25, -0.5, 0.0, None, &significance),
+            " 3.25 (-0.5, -13.3%) :green_circle: |"
+        );
+        assert_eq!(
+            format_cell(&AvgTime, 1.2, -0.004, 0.0, None, &significance),
+            " 1.2 |"
+        );
+        assert_eq!(
+            format_cell(&FlushTime, 5.0, 5.0, 0.0, None, &significance),
+            " 5 (+5) :red_circle: |"
+        );
+        assert_eq!(
+            format_cell(&CPU, 101.0, 1.0, 2.0, None, &significance),
+            " 101 _(+1, +1.0%)_ |"
+        );
+        assert_eq!(
+            format_cell(&CPU, 103.0, 3.0, 2.0, None, &significance),
+            " 103 (+3, +3.0%) :red_circle: |"
+        );
+
+        let stats = |p_value| MetricStats {
+            p_value: Some(p_value),
+            ..MetricStats::from_samples(vec![21.0, 22.0, 23.0])
+        };
+        assert_eq!(
+            format_cell(&FPS, 22.0, 3.0, 0.0, Some(&stats(0.012)), &significance),
+            " 22 ± 1 (+3, +15.8%, p=0.012) :green_circle: |"
+        );
+        assert_eq!(
+            format_cell(&FPS, 22.0, 3.0, 0.0, Some(&stats(0.0001)), &significance),
+            " 22 ± 1 (+3, +15.8%, p<0.001) :green_circle: |"
+        );
+        assert_eq!(
+            format_cell(&FPS, 22.0, 3.0, 0.0, Some(&stats(0.3)), &significance),
+            " 22 ± 1 _(+3, +15.8%, p=0.300)_ |"
+        );
     }
 
     #[test]
     fn test_format_table() {
         let scenes = vec![
-            Scene {
-                scene_name: "Single rectangle".to_string(),
-                avg_cpu: 65,
-                avg_fps: 19,
-                avg_time: 166,
-                render_time: 0,
-                flush_time: 166,
-            },
-            Scene {
-                scene_name: "Widgets demo".to_string(),
-                avg_cpu: 5,
-                avg_fps: 28,
-                avg_time: 0,
-                render_time: 0,
-                flush_time: 0,
-            },
+            create_scene("Single rectangle", 65.0, 19.0, 166.0, 0.0, 166.0),
+            create_scene("Widgets demo", 5.0, 28.0, 0.0, 0.0, 0.0),
         ];
         let deltas = vec![
-            Scene {
-                scene_name: "Single rectangle".to_string(),
-                avg_cpu: 5,
-                avg_fps: -3,
-                avg_time: 20,
-                render_time: 0,
-                flush_time: 20,
-            },
-            Scene {
-                scene_name: "Widgets demo".to_string(),
-                avg_cpu: 1,
-                avg_fps: -1,
-                avg_time: 0,
-                render_time: 0,
-                flush_time: 0,
-            },
+            create_scene("Single rectangle", 5.0, -3.0, 20.0, 0.0, 20.0),
+            create_scene("Widgets demo", 1.0, -1.0, 0.0, 0.0, 0.0),
         ];
 
-        let expected = "| Scene Name | Avg CPU (%) | Avg FPS | Avg Time (ms) | Render Time (ms) | Flush Time (ms) |\n\
-            |------------|-------------|---------|---------------|------------------|-----------------|\n\
-            | Single rectangle | 65 (+5) | 19 (-3) | 166 (+20) | 0 | 166 (+20) |\n\
-            | Widgets demo | 5 (+1) | 28 (-1) | 0 | 0 | 0 |\n";
+        let expected = "| Scene Name | CPU Usage (%) | FPS | Average Time (ms) | Render Time (ms) | Flush Time (ms) |\n\
+            |------------|---------------|-----|-------------------|------------------|-----------------|\n\
+            | Single rectangle | 65 (+5, +8.3%) :red_circle: | 19 (-3, -13.6%) :red_circle: | 166 (+20, +13.7%) :red_circle: | 0 | 166 (+20, +13.7%) :red_circle: |\n\
+            | Widgets demo | 5 (+1, +25.0%) :red_circle: | 28 (-1, -3.4%) :red_circle: | 0 | 0 | 0 |\n";
 
-        let actual = format_table(&scenes, &deltas);
+        let actual = format_table(
+            &compare_scenes(scenes.clone(), deltas),
+            &[],
+            &SceneMetric::PARSED,
+            &ThresholdConfig::default(),
+            &SignificanceConfig::default(),
+        );
+        assert_eq!(actual, expected);
+
+        let actual = format_table(
+            &[
+                SceneComparison::Added(scenes[0].clone()),
+                SceneComparison::Removed(scenes[1].clone()),
+                SceneComparison::NoBaseline(scenes[1].clone()),
+            ],
+            &[],
+            &SceneMetric::PARSED,
+            &ThresholdConfig::default(),
+            &SignificanceConfig::default(),
+        );
+        let expected = "| Scene Name | CPU Usage (%) | FPS | Average Time (ms) | Render Time (ms) | Flush Time (ms) |\n\
+            |------------|---------------|-----|-------------------|------------------|-----------------|\n\
+            | Single rectangle _(new)_ | 65 | 19 | 166 | 0 | 166 |\n\
+            | ~~Widgets demo~~ _(removed)_ | - | - | - | - | - |\n\
+            | Widgets demo | 5 | 28 | 0 | 0 | 0 |\n";
+        assert_eq!(actual, expected);
+
+        let mut scene = scenes[1].clone();
+        scene.derive_metrics(40.0);
+        let columns = MetricsConfig {
+            show: vec![SceneMetric::Score],
+            ..Default::default()
+        }
+        .columns();
+        let actual = format_table(
+            &[
+                SceneComparison::Removed(scene.clone()),
+                SceneComparison::NoBaseline(scene),
+            ],
+            &[],
+            &columns,
+            &ThresholdConfig::default(),
+            &SignificanceConfig::default(),
+        );
+        let expected = "| Scene Name | CPU Usage (%) | FPS | Average Time (ms) | Render Time (ms) | Flush Time (ms) | Score (%) |\n\
+            |------------|---------------|-----|-------------------|------------------|-----------------|-----------|\n\
+            | ~~Widgets demo~~ _(removed)_ | - | - | - | - | - | - |\n\
+            | Widgets demo | 5 | 28 | 0 | 0 | 0 | 70 |\n";
         assert_eq!(actual, expected);
     }
 
     #[test]
     fn test_generate_comment_with_all_scenes_avg_complete() {
-        let config = create_config("STM32F746 Discovery", vec!["embedded", "cortex-m7"]);
+        let config = create_config(
+            BOARD_ID,
+            "STM32F746 Discovery",
+            vec!["embedded", "cortex-m7"],
+        );
         let scenes = vec![
-            Scene {
-                scene_name: "Single rectangle".to_string(),
-                avg_cpu: 65,
-                avg_fps: 19,
-                avg_time: 166,
-                render_time: 10,
-                flush_time: 166,
-            },
-            Scene {
-                scene_name: "Widgets demo".to_string(),
-                avg_cpu: 5,
-                avg_fps: 28,
-                avg_time: 0,
-                render_time: 20,
-                flush_time: 0,
-            },
-            Scene {
-                scene_name: "All scenes avg.".to_string(),
-                avg_cpu: 4,
-                avg_fps: 28,
-                avg_time: 10,
-                render_time: 30,
-                flush_time: 10,
-            },
+            create_scene("Single rectangle", 65.0, 19.0, 166.0, 10.0, 166.0),
+            create_scene("Widgets demo", 5.0, 28.0, 0.0, 20.0, 0.0),
+            create_scene("All scenes avg.", 4.0, 28.0, 10.0, 30.0, 10.0),
         ];
         let deltas = vec![
-            Scene {
-                scene_name: "Single rectangle".to_string(),
-                avg_cpu: 5,
-                avg_fps: 30,
-                avg_time: 20,
-                render_time: 0,
-                flush_time: 20,
-            },
-            Scene {
-                scene_name: "Widgets demo".to_string(),
-                avg_cpu: 1,
-                avg_fps: -1,
-                avg_time: 0,
-                render_time: 0,
-                flush_time: 0,
-            },
-            Scene {
-                scene_name: "All scenes avg.".to_string(),
-                avg_cpu: 2,
-                avg_fps: 1,
-                avg_time: 5,
-                render_time: 0,
-                flush_time: 5,
-            },
+            create_scene("Single rectangle", 5.0, 30.0, 20.0, 0.0, 20.0),
+            create_scene("Widgets demo", 1.0, -1.0, 0.0, 0.0, 0.0),
+            create_scene("All scenes avg.", 2.0, 1.0, 5.0, 0.0, 5.0),
         ];
 
-        let results = vec![(config, scenes, deltas)];
-        let comment = generate_comment(&results);
+        let results = vec![create_comparison(config, BenchmarkRun::new(scenes), deltas)];
+        let comment = generate_comment(
+            &results,
+            &[],
+            &[],
+            None,
+            &ThresholdConfig::default(),
+            &SignificanceConfig::default(),
+            &MetricsConfig::default(),
+        );
 
         let expected = "Hi :wave:, thank you for your PR!\n\n\
             We've run some performance benchmarks. Here are the results:\n\n\
             #### 550e8400-e29b-41d4-a716-446655440000 - STM32F746 Discovery [embedded,cortex-m7]\n\n\
-            | Scene Name | Avg CPU (%) | Avg FPS | Avg Time (ms) | Render Time (ms) | Flush Time (ms) |\n\
-            |------------|-------------|---------|---------------|------------------|-----------------|\n\
-            | All scenes avg. | 4 (+2) | 28 (+1) | 10 (+5) | 30 | 10 (+5) |\n\
+            _Baseline matched by id_\n\n\
+            | Scene Name | CPU Usage (%) | FPS | Average Time (ms) | Render Time (ms) | Flush Time (ms) |\n\
+            |------------|---------------|-----|-------------------|------------------|-----------------|\n\
+            | All scenes avg. | 4 (+2, +100.0%) :red_circle: | 28 (+1, +3.7%) :green_circle: | 10 (+5, +100.0%) :red_circle: | 30 | 10 (+5, +100.0%) :red_circle: |\n\
             \n<details>\n\
             <summary>\n\
             Detailed Results Per Scene\n\
             </summary>\n\n\
-            | Scene Name | Avg CPU (%) | Avg FPS | Avg Time (ms) | Render Time (ms) | Flush Time (ms) |\n\
-            |------------|-------------|---------|---------------|------------------|-----------------|\n\
-            | Single rectangle | 65 (+5) | 19 (+30) | 166 (+20) | 10 | 166 (+20) |\n\
-            | Widgets demo | 5 (+1) | 28 (-1) | 0 | 20 | 0 |\n\
-            | All scenes avg. | 4 (+2) | 28 (+1) | 10 (+5) | 30 | 10 (+5) |\n\
+            | Scene Name | CPU Usage (%) | FPS | Average Time (ms) | Render Time (ms) | Flush Time (ms) |\n\
+            |------------|---------------|-----|-------------------|------------------|-----------------|\n\
+            | Single rectangle | 65 (+5, +8.3%) :red_circle: | 19 (+30, +272.7%) :green_circle: | 166 (+20, +13.7%) :red_circle: | 10 | 166 (+20, +13.7%) :red_circle: |\n\
+            | Widgets demo | 5 (+1, +25.0%) :red_circle: | 28 (-1, -3.4%) :red_circle: | 0 | 20 | 0 |\n\
+            | All scenes avg. | 4 (+2, +100.0%) :red_circle: | 28 (+1, +3.7%) :green_circle: | 10 (+5, +100.0%) :red_circle: | 30 | 10 (+5, +100.0%) :red_circle: |\n\
             \n\n</details>\n\n\
             \n\n---\n\n\
             :robot: This comment was automatically generated by a bot.";
@@ -222,53 +538,34 @@ mod tests {
 
     #[test]
     fn test_generate_comment_without_all_scenes_avg_complete() {
-        let config = create_config("STM32F746 Discovery", vec!["embedded"]);
+        let config = create_config(BOARD_ID, "STM32F746 Discovery", vec!["embedded"]);
         let scenes = vec![
-            Scene {
-                scene_name: "Single rectangle".to_string(),
-                avg_cpu: 65,
-                avg_fps: 19,
-                avg_time: 166,
-                render_time: 0,
-                flush_time: 166,
-            },
-            Scene {
-                scene_name: "Widgets demo".to_string(),
-                avg_cpu: 5,
-                avg_fps: 28,
-                avg_time: 0,
-                render_time: 0,
-                flush_time: 0,
-            },
+            create_scene("Single rectangle", 65.0, 19.0, 166.0, 0.0, 166.0),
+            create_scene("Widgets demo", 5.0, 28.0, 0.0, 0.0, 0.0),
         ];
         let deltas = vec![
-            Scene {
-                scene_name: "Single rectangle".to_string(),
-                avg_cpu: 5,
-                avg_fps: -3,
-                avg_time: 20,
-                render_time: 0,
-                flush_time: 20,
-            },
-            Scene {
-                scene_name: "Widgets demo".to_string(),
-                avg_cpu: 0,
-                avg_fps: 0,
-                avg_time: 0,
-                render_time: 0,
-                flush_time: 0,
-            },
+            create_scene("Single rectangle", 5.0, -3.0, 20.0, 0.0, 20.0),
+            create_scene("Widgets demo", 0.0, 0.0, 0.0, 0.0, 0.0),
         ];
 
-        let results = vec![(config, scenes, deltas)];
-        let comment = generate_comment(&results);
+        let results = vec![create_comparison(config, BenchmarkRun::new(scenes), deltas)];
+        let comment = generate_comment(
+            &results,
+            &[],
+            &[],
+            None,
+            &ThresholdConfig::default(),
+            &SignificanceConfig::default(),
+            &MetricsConfig::default(),
+        );
 
         let expected = "Hi :wave:, thank you for your PR!\n\n\
             We've run some performance benchmarks. Here are the results:\n\n\
             #### 550e8400-e29b-41d4-a716-446655440000 - STM32F746 Discovery [embedded]\n\n\
-            | Scene Name | Avg CPU (%) | Avg FPS | Avg Time (ms) | Render Time (ms) | Flush Time (ms) |\n\
-            |------------|-------------|---------|---------------|------------------|-----------------|\n\
-            | Single rectangle | 65 (+5) | 19 (-3) | 166 (+20) | 0 | 166 (+20) |\n\
+            _Baseline matched by id_\n\n\
+            | Scene Name | CPU Usage (%) | FPS | Average Time (ms) | Render Time (ms) | Flush Time (ms) |\n\
+            |------------|---------------|-----|-------------------|------------------|-----------------|\n\
+            | Single rectangle | 65 (+5, +8.3%) :red_circle: | 19 (-3, -13.6%) :red_circle: | 166 (+20, +13.7%) :red_circle: | 0 | 166 (+20, +13.7%) :red_circle: |\n\
             | Widgets demo | 5 | 28 | 0 | 0 | 0 |\n\
             \n\n---\n\n\
             :robot: This comment was automatically generated by a bot.";
@@ -278,56 +575,41 @@ mod tests {
 
     #[test]
     fn test_generate_comment_multiple_configs_complete() {
-        let config1 = create_config("Board A", vec!["fast"]);
-        let config2 = create_config("Board B", vec!["slow"]);
-
-        let scenes1 = vec![Scene {
-            scene_name: "Test scene".to_string(),
-            avg_cpu: 10,
-            avg_fps: 30,
-            avg_time: 5,
-            render_time: 2,
-            flush_time: 3,
-        }];
-        let deltas1 = vec![Scene {
-            scene_name: "Test scene".to_string(),
-            avg_cpu: 1,
-            avg_fps: -1,
-            avg_time: 0,
-            render_time: 0,
-            flush_time: 0,
-        }];
+        let config1 = create_config(BOARD_ID, "Board A", vec!["fast"]);
+        let config2 = create_config(BOARD_ID, "Board B", vec!["slow"]);
 
-        let scenes2 = vec![Scene {
-            scene_name: "Test scene".to_string(),
-            avg_cpu: 20,
-            avg_fps: 25,
-            avg_time: 10,
-            render_time: 5,
-            flush_time: 5,
-        }];
-        let deltas2 = vec![Scene {
-            scene_name: "Test scene".to_string(),
-            avg_cpu: -2,
-            avg_fps: 2,
-            avg_time: 1,
-            render_time: 0,
-            flush_time: 1,
-        }];
+        let scenes1 = vec![create_scene("Test scene", 10.0, 30.0, 5.0, 2.0, 3.0)];
+        let deltas1 = vec![create_scene("Test scene", 1.0, -1.0, 0.0, 0.0, 0.0)];
 
-        let results = vec![(config1, scenes1, deltas1), (config2, scenes2, deltas2)];
-        let comment = generate_comment(&results);
+        let scenes2 = vec![create_scene("Test scene", 20.0, 25.0, 10.0, 5.0, 5.0)];
+        let deltas2 = vec![create_scene("Test scene", -2.0, 2.0, 1.0, 0.0, 1.0)];
+
+        let results = vec![
+            create_comparison(config1, BenchmarkRun::new(scenes1), deltas1),
+            create_comparison(config2, BenchmarkRun::new(scenes2), deltas2),
+        ];
+        let comment = generate_comment(
+            &results,
+            &[],
+            &[],
+            None,
+            &ThresholdConfig::default(),
+            &SignificanceConfig::default(),
+            &MetricsConfig::default(),
+        );
 
         let expected = "Hi :wave:, thank you for your PR!\n\n\
             We've run some performance benchmarks. Here are the results:\n\n\
             #### 550e8400-e29b-41d4-a716-446655440000 - Board A [fast]\n\n\
-            | Scene Name | Avg CPU (%) | Avg FPS | Avg Time (ms) | Render Time (ms) | Flush Time (ms) |\n\
-            |------------|-------------|---------|---------------|------------------|-----------------|\n\
-            | Test scene | 10 (+1) | 30 (-1) | 5 | 2 | 3 |\n\
+            _Baseline matched by id_\n\n\
+            | Scene Name | CPU Usage (%) | FPS | Average Time (ms) | Render Time (ms) | Flush Time (ms) |\n\
+            |------------|---------------|-----|-------------------|------------------|-----------------|\n\
+            | Test scene | 10 (+1, +11.1%) :red_circle: | 30 (-1, -3.2%) :red_circle: | 5 | 2 | 3 |\n\
             #### 550e8400-e29b-41d4-a716-446655440000 - Board B [slow]\n\n\
-            | Scene Name | Avg CPU (%) | Avg FPS | Avg Time (ms) | Render Time (ms) | Flush Time (ms) |\n\
-            |------------|-------------|---------|---------------|------------------|-----------------|\n\
-            | Test scene | 20 (-2) | 25 (+2) | 10 (+1) | 5 | 5 (+1) |\n\
+            _Baseline matched by id_\n\n\
+            | Scene Name | CPU Usage (%) | FPS | Average Time (ms) | Render Time (ms) | Flush Time (ms) |\n\
+            |------------|---------------|-----|-------------------|------------------|-----------------|\n\
+            | Test scene | 20 (-2, -9.1%) :green_circle: | 25 (+2, +8.7%) :green_circle: | 10 (+1, +11.1%) :red_circle: | 5 | 5 (+1, +25.0%) :red_circle: |\n\
             \n\n---\n\n\
             :robot: This comment was automatically generated by a bot.";
 
@@ -336,14 +618,249 @@ mod tests {
 
     #[test]
     fn test_ej_board_config_display_complete() {
-        let config = EjBoardConfigApi {
-            id: Uuid::parse_str("550e8400-e29b-41d4-a716-446655440000").unwrap(),
-            name: "STM32F746 Discovery".to_string(),
-            tags: vec!["embedded".to_string(), "cortex-m7".to_string()],
-        };
+        let config = create_config(
+            BOARD_ID,
+            "STM32F746 Discovery",
+            vec!["embedded", "cortex-m7"],
+        );
 
         let expected =
             "550e8400-e29b-41d4-a716-446655440000 - STM32F746 Discovery [embedded,cortex-m7]";
         assert_eq!(format!("{}", config), expected);
     }
+
+    #[test]
+    fn test_generate_comment_multiple_runs_per_board() {
+        let config = create_config(BOARD_ID, "Board A", vec!["fast"]);
+        let scene = create_scene("Test scene", 10.0, 30.0, 5.0, 2.0, 3.0);
+        let mut small = BenchmarkRun::new(vec![scene.clone()]);
+        small
+            .metadata
+            .push(("Resolution".to_string(), "320x240".to_string()));
+        let mut large = BenchmarkRun::new(vec![scene.clone()]);
+        large
+            .metadata
+            .push(("Label".to_string(), "SDL".to_string()));
+        large
+            .metadata
+            .push(("Resolution".to_string(), "800x480".to_string()));
+        large.version = Some("9.4.0 dev".to_string());
+
+        let mut large = BoardComparison::new(config.clone(), large);
+        large.metadata_mismatches = vec!["Resolution: 640x480 → 800x480".to_string()];
+        let results = vec![BoardComparison::new(config, small), large];
+        let comment = generate_comment(
+            &results,
+            &[],
+            &[],
+            None,
+            &ThresholdConfig::default(),
+            &SignificanceConfig::default(),
+            &MetricsConfig::default(),
+        );
+
+        let expected = "Hi :wave:, thank you for your PR!\n\n\
+            We've run some performance benchmarks. Here are the results:\n\n\
+            #### 550e8400-e29b-41d4-a716-446655440000 - Board A [fast]\n\n\
+            _No baseline results for this board_\n\n\
+            ##### 320x240\n\n\
+            **Resolution** 320x240\n\n\
+            | Scene Name | CPU Usage (%) | FPS | Average Time (ms) | Render Time (ms) | Flush Time (ms) |\n\
+            |------------|---------------|-----|-------------------|------------------|-----------------|\n\
+            | Test scene | 10 | 30 | 5 | 2 | 3 |\n\
+            ##### SDL (800x480)\n\n\
+            **LVGL** 9.4.0 dev | **Label** SDL | **Resolution** 800x480\n\n\
+            > [!WARNING]\n\
+            > The baseline was run with different settings:\n\
+            > - Resolution: 640x480 → 800x480\n\
+            \n\
+            | Scene Name | CPU Usage (%) | FPS | Average Time (ms) | Render Time (ms) | Flush Time (ms) |\n\
+            |------------|---------------|-----|-------------------|------------------|-----------------|\n\
+            | Test scene | 10 | 30 | 5 | 2 | 3 |\n\
+            \n\n---\n\n\
+            :robot: This comment was automatically generated by a bot.";
+
+        assert_eq!(comment, expected);
+    }
+
+    #[test]
+    fn test_generate_comment_parse_failure() {
+        let config = create_config(BOARD_ID, "Board A", vec!["fast"]);
+        let diagnostic = crate::parser::parse_benchmark_runs(
+            "Benchmark Summary\nName, Avg. CPU, Avg. FPS, Avg. time, render time, flush time\nA, 1%, 1.2.3, 1, 1, 1",
+        )
+        .unwrap_err()
+        .with_source("Board A");
+
+        let outcomes = vec![(config, BoardOutcome::ParseFailed(diagnostic))];
+        let comment = generate_comment(
+            &[],
+            &outcomes,
+            &[],
+            None,
+            &ThresholdConfig::default(),
+            &SignificanceConfig::default(),
+            &MetricsConfig::default(),
+        );
+
+        let expected = "Hi :wave:, thank you for your PR!\n\n\
+            We've run some performance benchmarks. Here are the results:\n\n\
+            ### Board status\n\n\
+            | Board | Status |\n\
+            |-------|--------|\n\
+            | Board A | :x: Failed to parse results |\n\n\
+            #### 550e8400-e29b-41d4-a716-446655440000 - Board A [fast]\n\n\
+            :x: Failed to parse the benchmark results:\n\n\
+            ```text\n\
+            Board A: line 3, column 8: invalid value '1.2.3': invalid float literal\n  |\n\
+            3 | A, 1%, 1.2.3, 1, 1, 1\n  |        ^^^^^\n\
+            ```\n\n\
+            \n\n---\n\n\
+            :robot: This comment was automatically generated by a bot.";
+
+        assert_eq!(comment, expected);
+    }
+
+    #[test]
+    fn test_generate_comment_partial_results() {
+        let config_ok = create_config(BOARD_ID, "Board A", vec!["fast"]);
+        let config_skipped = create_config(BOARD_ID, "Board B", vec![]);
+        let config_failed = create_config(BOARD_ID, "Board C", vec![]);
+        let config_timeout = create_config(BOARD_ID, "Board D", vec![]);
+        let scenes = vec![create_scene("Test scene", 10.0, 30.0, 5.0, 2.0, 3.0)];
+        let run = BenchmarkRun::new(scenes);
+
+        let results = vec![BoardComparison::new(config_ok.clone(), run.clone())];
+        let outcomes = vec![
+            (config_ok, BoardOutcome::Ok(vec![run])),
+            (config_skipped, BoardOutcome::Skipped),
+            (
+                config_failed,
+                BoardOutcome::RunFailed("Flashing...\nHard fault".to_string()),
+            ),
+            (config_timeout, BoardOutcome::TimedOut),
+        ];
+        let comment = generate_comment(
+            &results,
+            &outcomes,
+            &[],
+            None,
+            &ThresholdConfig::default(),
+            &SignificanceConfig::default(),
+            &MetricsConfig::default(),
+        );
+
+        let expected = "Hi :wave:, thank you for your PR!\n\n\
+            We've run some performance benchmarks. Here are the results:\n\n\
+            ### Board status\n\n\
+            | Board | Status |\n\
+            |-------|--------|\n\
+            | Board A | :white_check_mark: Ok |\n\
+            | Board B | :fast_forward: Skipped |\n\
+            | Board C | :x: Run failed |\n\
+            | Board D | :hourglass: Timed out |\n\n\
+            #### 550e8400-e29b-41d4-a716-446655440000 - Board A [fast]\n\n\
+            _No baseline results for this board_\n\n\
+            | Scene Name | CPU Usage (%) | FPS | Average Time (ms) | Render Time (ms) | Flush Time (ms) |\n\
+            |------------|---------------|-----|-------------------|------------------|-----------------|\n\
+            | Test scene | 10 | 30 | 5 | 2 | 3 |\n\
+            #### 550e8400-e29b-41d4-a716-446655440000 - Board C []\n\n\
+            :x: The run failed. Last lines of the logs:\n\n\
+            ```text\nFlashing...\nHard fault\n```\n\n\
+            \n\n---\n\n\
+            :robot: This comment was automatically generated by a bot.";
+
+        assert_eq!(comment, expected);
+    }
+
+    #[test]
+    fn test_generate_comment_budget_violations() {
+        let config = create_config(BOARD_ID, "Board A", vec![]);
+        let budget = Budget {
+            scene: Some(SceneGlob::try_from("All scenes avg.".to_string()).unwrap()),
+            ..Budget::new(SceneMetric::FPS, 3.0)
+        };
+        let violations = vec![BudgetViolation {
+            board_config: config,
+            scene_name: "All scenes avg.".to_string(),
+            metric: SceneMetric::FPS,
+            change: -5.0,
+            budget,
+        }];
+        let baseline = Baseline {
+            origin: "latest `master` commit".to_string(),
+            skipped: 1,
+            ..create_baseline()
+        };
+        let comment = generate_comment(
+            &[],
+            &[],
+            &violations,
+            Some(&baseline),
+            &ThresholdConfig::default(),
+            &SignificanceConfig::default(),
+            &MetricsConfig::default(),
+        );
+
+        let expected = "Hi :wave:, thank you for your PR!\n\n\
+            We've run some performance benchmarks. Here are the results:\n\n\
+            _Compared against `0123456` (latest `master` commit), 1 newer commit(s) had no results_\n\n\
+            ### :rotating_light: Performance budget exceeded\n\n\
+            | Board | Scene | Metric | Change | Rule |\n\
+            |-------|-------|--------|--------|------|\n\
+            | Board A | All scenes avg. | FPS | -5.0% | All scenes avg.: FPS must not drop more than 3% |\n\n\
+            \n\n---\n\n\
+            :robot: This comment was automatically generated by a bot.";
+
+        assert_eq!(comment, expected);
+    }
+
+    #[test]
+    fn test_generate_comment_repetitions() {
+        let config = create_config(BOARD_ID, "Board A", vec![]);
+        let scene = |avg_fps: f64| create_scene("Test scene", 10.0, avg_fps, 5.0, 2.0, 3.0);
+        let run = BenchmarkRun::aggregate(&[
+            BenchmarkRun::new(vec![scene(29.0)]),
+            BenchmarkRun::new(vec![scene(31.0)]),
+        ]);
+        let delta = vec![create_scene("Test scene", 0.0, -2.0, 0.0, 0.0, 0.0)];
+
+        let mut comparison = create_comparison(config, run, delta);
+        comparison.baseline_match = Some(BoardMatch::Name);
+        let results = vec![comparison];
+        let comment = generate_comment(
+            &results,
+            &[],
+            &[],
+            None,
+            &ThresholdConfig::default(),
+            &SignificanceConfig::default(),
+            &MetricsConfig::default(),
+        );
+
+        let expected = "Hi :wave:, thank you for your PR!\n\n\
+            We've run some performance benchmarks. Here are the results:\n\n\
+            #### 550e8400-e29b-41d4-a716-446655440000 - Board A []\n\n\
+            _Baseline matched by name_\n\n\
+            _Mean ± standard deviation of 2 repetitions_\n\n\
+            | Scene Name | CPU Usage (%) | FPS | Average Time (ms) | Render Time (ms) | Flush Time (ms) |\n\
+            |------------|---------------|-----|-------------------|------------------|-----------------|\n\
+            | Test scene | 10 ± 0 | 30 ± 1.41 (-2, -6.2%) :red_circle: | 5 ± 0 | 2 ± 0 | 3 ± 0 |\n\
+            \n<details>\n\
+            <summary>\n\
+            Spread Over Repetitions\n\
+            </summary>\n\n\
+            | Scene Name | Metric | Mean | Median | Std Dev | Min | Max |\n\
+            |------------|--------|------|--------|---------|-----|-----|\n\
+            | Test scene | CPU Usage (%) | 10 | 10 | 0 | 10 | 10 |\n\
+            | Test scene | FPS | 30 | 30 | 1.41 | 29 | 31 |\n\
+            | Test scene | Average Time (ms) | 5 | 5 | 0 | 5 | 5 |\n\
+            | Test scene | Render Time (ms) | 2 | 2 | 0 | 2 | 2 |\n\
+            | Test scene | Flush Time (ms) | 3 | 3 | 0 | 3 | 3 |\n\
+            \n\n</details>\n\n\
+            \n\n---\n\n\
+            :robot: This comment was automatically generated by a bot.";
+
+        assert_eq!(comment, expected);
+    }
 }
@@ -0,0 +1,650 @@
+//! Project configuration (`ejlv.toml`).
+//!
+//! Every value in the configuration file is a default for the matching CLI flag.
+//! Flags (and their environment variables) always take precedence.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use crate::budget::{Budget, load_budgets};
+use crate::cli::{DispatchArgs, GhArgs};
+use crate::gh::BaselineSource;
+use crate::matching::BoardMapping;
+use crate::prelude::*;
+use crate::scene::{BenchmarkRun, SceneAliases, SceneMetric};
+use crate::stats::{BaselineStatistic, SignificanceTest};
+
+/// Configuration file looked up in the current directory when `--config` isn't given.
+pub const DEFAULT_CONFIG_PATH: &str = "ejlv.toml";
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Budgets file whose rules are added to `budgets`, relative to the configuration file
+    pub budgets_file: Option<PathBuf>,
+    /// Board mapping file pairing renamed board configs with their baseline, relative to the
+    /// configuration file
+    pub board_mapping_file: Option<PathBuf>,
+    pub github: GithubConfig,
+    pub dispatch: DispatchConfig,
+    pub output: OutputConfig,
+    pub thresholds: ThresholdConfig,
+    pub significance: SignificanceConfig,
+    pub baseline: BaselineConfig,
+    pub metrics: MetricsConfig,
+    /// Old scene names compared with their new name, `[scene_aliases]` in the configuration file
+    pub scene_aliases: SceneAliases,
+    /// Budgets checked against the deltas, `[[budgets]]` tables in the configuration file
+    pub budgets: Vec<Budget>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GithubConfig {
+    pub owner: String,
+    pub repo: String,
+    pub baseline_branch: String,
+    pub signature: Option<String>,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DispatchConfig {
+    pub socket: Option<PathBuf>,
+    pub seconds: Option<u64>,
+    pub remote_url: Option<String>,
+    /// Number of times every run is dispatched, and of baseline results aggregated
+    pub repetitions: Option<usize>,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OutputConfig {
+    pub comment_path: Option<PathBuf>,
+    /// Results export in JSON, see `--json-out`
+    pub json_out: Option<PathBuf>,
+    /// Results export in CSV, see `--csv-out`
+    pub csv_out: Option<PathBuf>,
+    /// JUnit XML report of the budget checks, see `--junit-out`
+    pub junit_out: Option<PathBuf>,
+}
+
+/// Relative change (in percent) below which a metric delta is considered noise.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ThresholdConfig {
+    pub cpu: f64,
+    pub fps: f64,
+    pub avg_time: f64,
+    pub render_time: f64,
+    pub flush_time: f64,
+    pub render_share: f64,
+    pub frame_headroom: f64,
+    pub score: f64,
+}
+
+/// How changes are tested when both the baseline and the PR have several samples.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SignificanceConfig {
+    pub test: SignificanceTest,
+    /// Changes with a p-value at or above `alpha` aren't flagged as regressions or improvements
+    pub alpha: f64,
+}
+
+/// How the baseline results of several commits are combined.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BaselineConfig {
+    /// Number of first-parent commits with results combined into the baseline
+    pub window: Option<usize>,
+    pub statistic: BaselineStatistic,
+}
+
+/// How the derived metrics are computed and which ones are shown in the comment.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MetricsConfig {
+    /// Frame rate the frame headroom and the score are relative to
+    pub target_fps: f64,
+    /// Weight of the scenes combined into the `Weighted score` scene, other scenes are left out
+    pub scene_weights: BTreeMap<String, f64>,
+    /// Derived metrics added as columns of the comment tables
+    pub show: Vec<SceneMetric>,
+}
+
+/// Where the baseline comes from and how it's combined, with every CLI and configuration value
+/// resolved.
+pub struct BaselineOptions {
+    pub source: BaselineSource,
+    pub board_mapping: BoardMapping,
+    pub scene_aliases: SceneAliases,
+    pub window: usize,
+    pub statistic: BaselineStatistic,
+    /// Token of the GitHub API requests, which are rate limited without it
+    pub gh_token: Option<String>,
+}
+
+/// How the results of a run are reported, with every CLI and configuration value resolved.
+pub struct ReportOptions {
+    pub comment_path: PathBuf,
+    pub json_out: Option<PathBuf>,
+    pub csv_out: Option<PathBuf>,
+    pub junit_out: Option<PathBuf>,
+    pub thresholds: ThresholdConfig,
+    pub significance: SignificanceConfig,
+    pub metrics: MetricsConfig,
+    pub budgets: Vec<Budget>,
+    pub fail_on_regression: bool,
+}
+
+/// A dispatch job with every CLI and configuration value resolved.
+pub struct DispatchJob {
+    pub seconds: u64,
+    pub commit_hash: String,
+    pub remote_url: String,
+    pub remote_token: Option<String>,
+}
+
+impl Default for GithubConfig {
+    fn default() -> Self {
+        Self {
+            owner: "lvgl".to_string(),
+            repo: "lvgl".to_string(),
+            baseline_branch: "master".to_string(),
+            signature: None,
+        }
+    }
+}
+
+impl Default for SignificanceConfig {
+    fn default() -> Self {
+        Self {
+            test: SignificanceTest::Welch,
+            alpha: 0.05,
+        }
+    }
+}
+
+impl Default for MetricsConfig {
+    fn default() -> Self {
+        Self {
+            target_fps: 30.0,
+            scene_weights: BTreeMap::new(),
+            show: Vec::new(),
+        }
+    }
+}
+
+impl Config {
+    /// Loads the configuration from `path`.
+    ///
+    /// Without an explicit path, `ejlv.toml` is used if it exists and the built-in defaults otherwise.
+    pub fn load(path: Option<&Path>) -> Result<Self> {
+        let path = match path {
+            Some(path) => path,
+            None => {
+                let path = Path::new(DEFAULT_CONFIG_PATH);
+                if !path.is_file() {
+                    return Ok(Self::default());
+                }
+                path
+            }
+        };
+        info!("Loading configuration from {}", path.display());
+        let content = std::fs::read_to_string(path)?;
+        let mut config =
+            Self::parse(&content).map_err(|err| Error::InvalidConfig(path.to_path_buf(), err))?;
+        if let Some(dir) = path.parent() {
+            config.resolve_paths(dir);
+        }
+        config.metrics.validate()?;
+        Ok(config)
+    }
+
+    /// Makes the files referenced by the configuration relative to `dir`, its directory.
+    fn resolve_paths(&mut self, dir: &Path) {
+        for path in [&mut self.budgets_file, &mut self.board_mapping_file]
+            .into_iter()
+            .flatten()
+        {
+            *path = dir.join(&*path);
+        }
+    }
+
+    pub fn parse(content: &str) -> core::result::Result<Self, toml::de::Error> {
+        toml::from_str(content)
+    }
+
+    pub fn to_toml(&self) -> Result<String> {
+        Ok(toml::to_string_pretty(self)?)
+    }
+
+    /// Board mapping of the board mapping file, if any.
+    pub fn board_mapping(&self, board_mapping_file: Option<PathBuf>) -> Result<BoardMapping> {
+        match board_mapping_file.or_else(|| self.board_mapping_file.clone()) {
+            Some(path) => BoardMapping::load(&path),
+            None => Ok(BoardMapping::default()),
+        }
+    }
+
+    /// Budgets of the configuration file followed by the ones of the budgets file, if any.
+    pub fn budgets(&self, budgets_file: Option<PathBuf>) -> Result<Vec<Budget>> {
+        let mut budgets = self.budgets.clone();
+        if let Some(path) = budgets_file.or_else(|| self.budgets_file.clone()) {
+            budgets.extend(load_budgets(&path)?);
+        }
+        Ok(budgets)
+    }
+}
+
+impl GithubConfig {
+    pub fn merge(&mut self, args: GhArgs) {
+        if let Some(owner) = args.owner {
+            self.owner = owner;
+        }
+        if let Some(repo) = args.repo {
+            self.repo = repo;
+        }
+        if let Some(baseline_branch) = args.baseline_branch {
+            self.baseline_branch = baseline_branch;
+        }
+    }
+
+    pub fn signature(&self, signature: Option<String>) -> Result<String> {
+        resolve(
+            signature,
+            &self.signature,
+            "--signature",
+            "github.signature",
+        )
+    }
+}
+
+impl DispatchConfig {
+    pub fn socket(&self, socket: Option<PathBuf>) -> Result<PathBuf> {
+        resolve(socket, &self.socket, "--socket", "dispatch.socket")
+    }
+
+    pub fn job(&self, args: DispatchArgs) -> Result<DispatchJob> {
+        Ok(DispatchJob {
+            seconds: resolve(args.seconds, &self.seconds, "--seconds", "dispatch.seconds")?,
+            commit_hash: args.commit_hash,
+            remote_url: resolve(
+                args.remote_url,
+                &self.remote_url,
+                "--remote-url",
+                "dispatch.remote_url",
+            )?,
+            remote_token: args.remote_token,
+        })
+    }
+
+    pub fn repetitions(&self, repetitions: Option<usize>) -> usize {
+        repetitions.or(self.repetitions).unwrap_or(1).max(1)
+    }
+}
+
+impl BaselineConfig {
+    pub fn window(&self, window: Option<usize>) -> usize {
+        window.or(self.window).unwrap_or(1).max(1)
+    }
+}
+
+impl OutputConfig {
+    pub fn comment_path(&self, comment_path: Option<PathBuf>) -> Result<PathBuf> {
+        resolve(
+            comment_path,
+            &self.comment_path,
+            "--comment-path",
+            "output.comment_path",
+        )
+    }
+
+    pub fn json_out(&self, json_out: Option<PathBuf>) -> Option<PathBuf> {
+        json_out.or_else(|| self.json_out.clone())
+    }
+
+    pub fn csv_out(&self, csv_out: Option<PathBuf>) -> Option<PathBuf> {
+        csv_out.or_else(|| self.csv_out.clone())
+    }
+
+    pub fn junit_out(&self, junit_out: Option<PathBuf>) -> Option<PathBuf> {
+        junit_out.or_else(|| self.junit_out.clone())
+    }
+
+    /// The JSON export read by `publish-check`, defaults to `output.json_out`.
+    pub fn results(&self, results: Option<PathBuf>) -> Result<PathBuf> {
+        resolve(results, &self.json_out, "--results", "output.json_out")
+    }
+}
+
+impl ThresholdConfig {
+    pub fn get(&self, metric: &SceneMetric) -> f64 {
+        match metric {
+            SceneMetric::CPU => self.cpu,
+            SceneMetric::FPS => self.fps,
+            SceneMetric::AvgTime => self.avg_time,
+            SceneMetric::RenderTime => self.render_time,
+            SceneMetric::FlushTime => self.flush_time,
+            SceneMetric::RenderShare => self.render_share,
+            SceneMetric::FrameHeadroom => self.frame_headroom,
+            SceneMetric::Score => self.score,
+        }
+    }
+}
+
+impl MetricsConfig {
+    /// Checks that the derived metrics can be computed, i.e. that `target_fps` is positive.
+    pub fn validate(&self) -> Result<()> {
+        if self.target_fps <= 0.0 {
+            return Err(Error::InvalidTargetFps(self.target_fps));
+        }
+        Ok(())
+    }
+
+    /// Computes the derived metrics of every scene of `run` and adds its weighted score scene.
+    pub fn apply(&self, run: &mut BenchmarkRun) -> Result<()> {
+        self.validate()?;
+        for scene in &mut run.scenes {
+            scene.derive_metrics(self.target_fps);
+        }
+        run.add_weighted_scene(&self.scene_weights);
+        Ok(())
+    }
+
+    /// Metrics shown in the comment tables, the parsed ones followed by the derived ones in `show`.
+    pub fn columns(&self) -> Vec<SceneMetric> {
+        let derived = self.show.iter().filter(|metric| metric.is_derived());
+        SceneMetric::PARSED.iter().chain(derived).cloned().collect()
+    }
+
+    /// Metrics classified as improvements or regressions: the parsed ones, followed by the derived
+    /// ones in `show` or with a budget.
+    ///
+    /// Other derived metrics are left out, as they would repeat the change of the metric they're
+    /// derived from.
+    pub fn classified(&self, budgets: &[Budget]) -> Vec<SceneMetric> {
+        SceneMetric::ALL
+            .iter()
+            .filter(|metric| {
+                !metric.is_derived()
+                    || self.show.contains(metric)
+                    || budgets.iter().any(|budget| budget.metric == **metric)
+            })
+            .cloned()
+            .collect()
+    }
+}
+
+impl SignificanceConfig {
+    /// Whether a change is statistically significant, changes that couldn't be tested are.
+    pub fn is_significant(&self, p_value: Option<f64>) -> bool {
+        p_value.is_none_or(|p_value| p_value < self.alpha)
+    }
+
+    /// P-value of the difference between both samples, see [`SignificanceTest::p_value`].
+    ///
+    /// Returns `None` as well when the samples are too small for the test to ever reach `alpha`,
+    /// so the change is classified by the thresholds alone rather than never flagged.
+    pub fn p_value(&self, a: &[f64], b: &[f64]) -> Option<f64> {
+        self.test
+            .p_value(a, b)
+            .filter(|_| self.reaches_alpha(a.len(), b.len()))
+    }
+
+    /// Whether the test can find samples of `n_a` and `n_b` values significant.
+    pub fn reaches_alpha(&self, n_a: usize, n_b: usize) -> bool {
+        self.test.min_p_value(n_a, n_b) < self.alpha
+    }
+}
+
+fn resolve<T: Clone>(
+    cli: Option<T>,
+    config: &Option<T>,
+    flag: &'static str,
+    key: &'static str,
+) -> Result<T> {
+    cli.or_else(|| config.clone())
+        .ok_or(Error::MissingSetting(flag, key))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_partial_config_keeps_defaults() {
+        let config = Config::parse(
+            r#"
+[github]
+repo = "lvgl-fork"
+
+[dispatch]
+socket = "/tmp/ejd.sock"
+seconds = 600
+"#,
+        )
+        .expect("Failed to parse config");
+
+        assert_eq!(config.github.owner, "lvgl");
+        assert_eq!(config.github.repo, "lvgl-fork");
+        assert_eq!(config.github.baseline_branch, "master");
+        assert_eq!(config.dispatch.socket, Some(PathBuf::from("/tmp/ejd.sock")));
+        assert_eq!(config.dispatch.seconds, Some(600));
+        assert_eq!(config.dispatch.remote_url, None);
+        assert_eq!(config.thresholds.get(&SceneMetric::FPS), 0.0);
+    }
+
+    #[test]
+    fn test_parse_thresholds() {
+        let config = Config::parse("[thresholds]\nfps = 2.5\nrender_time = 5\n")
+            .expect("Failed to parse config");
+
+        assert_eq!(config.thresholds.get(&SceneMetric::FPS), 2.5);
+        assert_eq!(config.thresholds.get(&SceneMetric::RenderTime), 5.0);
+        assert_eq!(config.thresholds.get(&SceneMetric::CPU), 0.0);
+    }
+
+    #[test]
+    fn test_parse_budgets() {
+        let config = Config::parse(
+            r#"
+[[budgets]]
+scene = "All scenes avg."
+metric = "fps"
+max_regression = 3
+
+[[budgets]]
+metric = "render_time"
+max_regression = 20
+"#,
+        )
+        .expect("Failed to parse config");
+
+        assert_eq!(config.budgets.len(), 2);
+        assert_eq!(
+            config.budgets[0].scene.clone().map(String::from).as_deref(),
+            Some("All scenes avg.")
+        );
+        assert_eq!(config.budgets[0].metric, SceneMetric::FPS);
+        assert_eq!(config.budgets[1].scene, None);
+        assert_eq!(config.budgets[1].max_regression, 20.0);
+        assert!(config.to_toml().is_ok());
+    }
+
+    #[test]
+    fn test_parse_significance() {
+        let config = Config::default();
+        assert_eq!(config.significance.test, SignificanceTest::Welch);
+        assert!(config.significance.is_significant(None));
+        assert!(config.significance.is_significant(Some(0.01)));
+        assert!(!config.significance.is_significant(Some(0.2)));
+
+        let config = Config::parse("[significance]\ntest = \"mann_whitney\"\nalpha = 0.01\n")
+            .expect("Failed to parse config");
+        assert_eq!(config.significance.test, SignificanceTest::MannWhitney);
+        assert!(!config.significance.is_significant(Some(0.02)));
+    }
+
+    #[test]
+    fn test_parse_baseline() {
+        let config = Config::default();
+        assert_eq!(config.baseline.window(None), 1);
+        assert_eq!(config.baseline.statistic, BaselineStatistic::Mean);
+
+        let config = Config::parse("[baseline]\nwindow = 5\nstatistic = \"trimmed_mean\"\n")
+            .expect("Failed to parse config");
+        assert_eq!(config.baseline.window(None), 5);
+        assert_eq!(config.baseline.window(Some(3)), 3);
+        assert_eq!(config.baseline.statistic, BaselineStatistic::TrimmedMean);
+    }
+
+    #[test]
+    fn test_classified_metrics() {
+        let metrics = MetricsConfig {
+            show: vec![SceneMetric::Score],
+            ..MetricsConfig::default()
+        };
+        let budgets = vec![Budget::new(SceneMetric::FrameHeadroom, 10.0)];
+        let mut expected = SceneMetric::PARSED.to_vec();
+        assert_eq!(MetricsConfig::default().classified(&[]), expected);
+        expected.extend([SceneMetric::FrameHeadroom, SceneMetric::Score]);
+        assert_eq!(metrics.classified(&budgets), expected);
+    }
+
+    #[test]
+    fn test_resolve_paths() {
+        let mut config = Config::parse(
+            "budgets_file = \"budgets.toml\"\nboard_mapping_file = \"/etc/ejlv/boards.toml\"\n",
+        )
+        .expect("Failed to parse config");
+        config.resolve_paths(Path::new("ci/perf"));
+        assert_eq!(
+            config.budgets_file,
+            Some(PathBuf::from("ci/perf/budgets.toml"))
+        );
+        assert_eq!(
+            config.board_mapping_file,
+            Some(PathBuf::from("/etc/ejlv/boards.toml"))
+        );
+
+        let mut config = Config::default();
+        config.resolve_paths(Path::new("ci/perf"));
+        assert_eq!(config.budgets_file, None);
+    }
+
+    #[test]
+    fn test_invalid_target_fps() {
+        let metrics = MetricsConfig {
+            target_fps: 0.0,
+            ..MetricsConfig::default()
+        };
+        let mut run = BenchmarkRun::new(vec![]);
+        assert!(matches!(
+            metrics.apply(&mut run),
+            Err(Error::InvalidTargetFps(fps)) if fps == 0.0
+        ));
+        assert!(MetricsConfig::default().apply(&mut run).is_ok());
+    }
+
+    #[test]
+    fn test_parse_metrics() {
+        let config = Config::default();
+        assert_eq!(config.metrics.target_fps, 30.0);
+        assert_eq!(config.metrics.columns(), SceneMetric::PARSED);
+
+        let config = Config::parse(
+            r#"
+[metrics]
+target_fps = 60
+show = ["score", "fps", "render_share"]
+
+[metrics.scene_weights]
+"Widgets demo" = 2
+
+[[budgets]]
+metric = "frame_headroom"
+max_regression = 10
+"#,
+        )
+        .expect("Failed to parse config");
+        assert_eq!(config.metrics.target_fps, 60.0);
+        assert_eq!(config.metrics.scene_weights.get("Widgets demo"), Some(&2.0));
+        assert_eq!(
+            config.metrics.columns()[SceneMetric::PARSED.len()..],
+            [SceneMetric::Score, SceneMetric::RenderShare]
+        );
+        assert_eq!(config.budgets[0].metric, SceneMetric::FrameHeadroom);
+        assert!(config.to_toml().is_ok());
+    }
+
+    #[test]
+    fn test_parse_scene_aliases() {
+        let config = Config::parse(
+            "[scene_aliases]\n\"Multiple RGB images\" = \"Multiple opaque images\"\n",
+        )
+        .expect("Failed to parse config");
+        assert!(
+            config
+                .scene_aliases
+                .same_scene("Multiple RGB images", "multiple opaque images")
+        );
+        assert!(config.to_toml().is_ok());
+    }
+
+    #[test]
+    fn test_parse_rejects_unknown_keys() {
+        assert!(Config::parse("[github]\nbranch = \"main\"\n").is_err());
+    }
+
+    #[test]
+    fn test_cli_takes_precedence() {
+        let mut config = Config::parse(
+            r#"
+[github]
+owner = "embj-org"
+baseline_branch = "main"
+
+[dispatch]
+seconds = 600
+remote_url = "https://github.com/embj-org/lvgl.git"
+"#,
+        )
+        .expect("Failed to parse config");
+
+        config.github.merge(GhArgs {
+            owner: None,
+            repo: Some("product".to_string()),
+            baseline_branch: Some("develop".to_string()),
+        });
+        assert_eq!(config.github.owner, "embj-org");
+        assert_eq!(config.github.repo, "product");
+        assert_eq!(config.github.baseline_branch, "develop");
+
+        let job = config
+            .dispatch
+            .job(DispatchArgs {
+                seconds: Some(30),
+                commit_hash: "abc".to_string(),
+                remote_url: None,
+                remote_token: None,
+            })
+            .expect("Failed to resolve job");
+        assert_eq!(job.seconds, 30);
+        assert_eq!(job.remote_url, "https://github.com/embj-org/lvgl.git");
+    }
+
+    #[test]
+    fn test_missing_setting() {
+        let config = Config::default();
+        let err = config.dispatch.socket(None).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::MissingSetting("--socket", "dispatch.socket")
+        ));
+    }
+}
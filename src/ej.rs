@@ -8,26 +8,55 @@ use tracing::{info, warn};
 
 use crate::prelude::*;
 
-pub async fn fetch_latest_run_result_from_commit(
+/// Fetches the results of the `count` latest run jobs associated with `commit`.
+pub async fn fetch_run_results_from_commit(
     socket: &Path,
     commit: String,
-) -> Result<Option<EjRunResult>> {
+    count: usize,
+) -> Result<Vec<EjRunResult>> {
     info!("Fecthing jobs associated with commit {commit}");
     let mut jobs = fetch_jobs(socket, commit.clone()).await?;
-    jobs = jobs
-        .into_iter()
-        .filter(|job| job.job_type == EjJobType::BuildAndRun)
-        .collect();
-    if jobs.len() > 1 {
-        warn!("Found multiple jobs associated with commit '{commit}'. Using latest one");
-        EjJobApi::sort_by_finished_desc(&mut jobs);
-    } else if jobs.len() == 1 {
-        info!("Found 1 job associate with commit '{commit}'");
+    jobs.retain(|job| job.job_type == EjJobType::BuildAndRun);
+    if jobs.len() > count {
+        warn!(
+            "Found {} jobs associated with commit '{commit}'. Using latest {count}",
+            jobs.len()
+        );
+    } else if !jobs.is_empty() {
+        info!(
+            "Found {} job(s) associated with commit '{commit}'",
+            jobs.len()
+        );
     } else {
         info!("No job associated with commit '{commit}'");
-        return Ok(None);
+        return Ok(Vec::new());
     };
-    let job = &jobs[0];
+    EjJobApi::sort_by_finished_desc(&mut jobs);
 
-    Ok(Some(fetch_run_result(socket, job.id).await?))
+    let mut results = Vec::new();
+    for job in jobs.iter().take(count) {
+        results.push(fetch_run_result(socket, job.id).await?);
+    }
+    Ok(results)
+}
+
+/// Fetches the results of the first `commits` commits of `history` that have any, with their
+/// position in `history`.
+pub async fn find_run_results_in_history(
+    socket: &Path,
+    history: &[String],
+    commits: usize,
+    count: usize,
+) -> Result<Vec<(usize, Vec<EjRunResult>)>> {
+    let mut found = Vec::new();
+    for (i, commit) in history.iter().enumerate() {
+        if found.len() == commits {
+            break;
+        }
+        let results = fetch_run_results_from_commit(socket, commit.clone(), count).await?;
+        if !results.is_empty() {
+            found.push((i, results));
+        }
+    }
+    Ok(found)
 }
@@ -1,19 +1,19 @@
-use std::{num::ParseIntError, path::PathBuf};
+use std::path::PathBuf;
 
 use ej_dispatcher_sdk::EjRunResult;
 use plotters::prelude::{DrawingBackend, SVGBackend};
 
+use crate::parser::ParseDiagnostic;
+
 /// Main error type
 #[derive(thiserror::Error, Debug)]
+#[allow(clippy::enum_variant_names)]
 pub enum Error {
     #[error(transparent)]
     DispactherSDK(#[from] ej_dispatcher_sdk::error::Error),
 
-    #[error("Invalid column at line {0}, col {1}: '{2}'")]
-    InvalidResultColumn(usize, usize, String),
-
-    #[error("Parse failed for column at line {0}, col {1}: '{2}' - {3}")]
-    ParseIntFailed(usize, usize, String, ParseIntError),
+    #[error(transparent)]
+    Parse(#[from] ParseDiagnostic),
 
     #[error("Result slice is empty")]
     ResultSliceEmpty,
@@ -27,8 +27,8 @@ pub enum Error {
     #[error(transparent)]
     IO(#[from] std::io::Error),
 
-    #[error("Failed to fetch latest master commit")]
-    FailedToFetchMasterCommit,
+    #[error("Failed to fetch latest commit from branch '{0}'")]
+    FailedToFetchBaselineCommit(String),
 
     #[error(transparent)]
     Plotters(
@@ -47,6 +47,29 @@ pub enum Error {
     #[error("Failed to convert file path to string {0}")]
     FilePathConversionFailed(PathBuf),
 
+    #[error("Invalid configuration file {0}: {1}")]
+    InvalidConfig(PathBuf, toml::de::Error),
+
+    #[error("'metrics.target_fps' must be positive, got {0}")]
+    InvalidTargetFps(f64),
+
+    #[error(transparent)]
+    ConfigSerialize(#[from] toml::ser::Error),
+
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+
+    #[error("Missing '{0}' (can also be set with '{1}' in the configuration file)")]
+    MissingSetting(&'static str, &'static str),
+
+    #[error("{0} performance budget violation(s)")]
+    BudgetExceeded(usize),
+
+    #[error(
+        "'--fail-on-regression' requires at least one budget, from '--budgets', 'budgets_file' or '[[budgets]]' in the configuration file"
+    )]
+    NoBudgets,
+
     #[error("Run error {0:?}")]
     RunError(EjRunResult),
 }
@@ -0,0 +1,347 @@
+//! Machine readable export of the compared results (`--json-out` and `--csv-out`).
+
+use serde::{Deserialize, Serialize};
+
+use ej_config::ej_board_config::EjBoardConfigApi;
+
+use crate::budget::BudgetViolation;
+use crate::config::{SignificanceConfig, ThresholdConfig};
+use crate::gh::Baseline;
+use crate::prelude::*;
+use crate::result::{BoardComparison, Change, SceneComparison, classify_delta, relative_delta};
+use crate::scene::SceneMetric;
+
+/// Version of the exported schema, bumped on every breaking change of its fields.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Columns of the CSV export, one row per board run, scene and metric.
+const CSV_HEADER: &str = "schema_version,board_id,board_name,run,scene,status,metric,value,baseline,delta,relative_delta,p_value,change";
+
+/// The results of a run compared with the baseline.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResultExport {
+    pub schema_version: u32,
+    /// `None` when no baseline result was found
+    pub baseline: Option<Baseline>,
+    /// One entry per benchmark run, like [`crate::result::calculate_result_delta`]
+    pub boards: Vec<BoardExport>,
+    /// Scene metrics that regressed more than their budget allows
+    pub violations: Vec<BudgetViolation>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BoardExport {
+    #[serde(flatten)]
+    pub comparison: BoardComparison,
+    /// Classification of the metrics of the compared scenes
+    pub changes: Vec<MetricChange>,
+}
+
+/// How a scene metric changed since the baseline.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MetricChange {
+    pub scene_name: String,
+    pub metric: SceneMetric,
+    pub value: f64,
+    pub baseline: f64,
+    pub delta: f64,
+    /// Change relative to the baseline in percent, `None` when the baseline is zero
+    pub relative_delta: Option<f64>,
+    pub p_value: Option<f64>,
+    pub change: Change,
+}
+
+impl ResultExport {
+    /// Classifies `metrics` of the compared scenes like the PR comment does, see
+    /// [`crate::config::MetricsConfig::classified`].
+    pub fn new(
+        results: &[BoardComparison],
+        violations: &[BudgetViolation],
+        baseline: Option<&Baseline>,
+        metrics: &[SceneMetric],
+        thresholds: &ThresholdConfig,
+        significance: &SignificanceConfig,
+    ) -> Self {
+        let boards = results
+            .iter()
+            .map(|comparison| BoardExport {
+                changes: metric_changes(comparison, metrics, thresholds, significance),
+                comparison: comparison.clone(),
+            })
+            .collect();
+        Self {
+            schema_version: SCHEMA_VERSION,
+            baseline: baseline.cloned(),
+            boards,
+            violations: violations.to_vec(),
+        }
+    }
+
+    /// Every metric classified as a regression, with its board config.
+    pub fn regressions(&self) -> Vec<(&EjBoardConfigApi, &MetricChange)> {
+        self.boards
+            .iter()
+            .flat_map(|board| {
+                board
+                    .changes
+                    .iter()
+                    .filter(|change| change.change == Change::Regression)
+                    .map(|change| (&board.comparison.board_config, change))
+            })
+            .collect()
+    }
+
+    /// Whether a regression exceeded its budget.
+    pub fn exceeds_budget(&self, board_config: &EjBoardConfigApi, change: &MetricChange) -> bool {
+        self.violations.iter().any(|violation| {
+            violation.board_config == *board_config
+                && violation.scene_name == change.scene_name
+                && violation.metric == change.metric
+        })
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Lists every metric of every scene, the baseline columns are empty for scenes that weren't
+    /// compared and the value is empty for removed scenes.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::new();
+        csv += CSV_HEADER;
+        csv += "\n";
+        for board in &self.boards {
+            let comparison = &board.comparison;
+            let run = comparison.run.title().unwrap_or_default();
+            for scene in &comparison.scenes {
+                for metric in &SceneMetric::ALL {
+                    let value = scene.scene().get_value(metric);
+                    let change = board.changes.iter().find(|change| {
+                        change.scene_name == scene.scene_name() && change.metric == *metric
+                    });
+                    let (value, baseline) = match scene {
+                        SceneComparison::Removed(_) => (String::new(), value.to_string()),
+                        _ => (
+                            value.to_string(),
+                            change.map_or(String::new(), |change| change.baseline.to_string()),
+                        ),
+                    };
+                    let optional =
+                        |value: Option<f64>| value.map_or(String::new(), |v| v.to_string());
+                    let row = [
+                        SCHEMA_VERSION.to_string(),
+                        comparison.board_config.id.to_string(),
+                        comparison.board_config.name.clone(),
+                        run.clone(),
+                        scene.scene_name().to_string(),
+                        scene.status().to_string(),
+                        metric.snake_case().to_string(),
+                        value,
+                        baseline,
+                        optional(change.map(|change| change.delta)),
+                        optional(change.and_then(|change| change.relative_delta)),
+                        optional(change.and_then(|change| change.p_value)),
+                        change.map_or(String::new(), |change| change_name(change.change)),
+                    ];
+                    let row: Vec<String> = row.iter().map(|field| csv_field(field)).collect();
+                    csv += &row.join(",");
+                    csv += "\n";
+                }
+            }
+        }
+        csv
+    }
+}
+
+fn metric_changes(
+    comparison: &BoardComparison,
+    metrics: &[SceneMetric],
+    thresholds: &ThresholdConfig,
+    significance: &SignificanceConfig,
+) -> Vec<MetricChange> {
+    let mut changes = Vec::new();
+    for scene in &comparison.scenes {
+        let SceneComparison::Compared { scene, delta } = scene else {
+            continue;
+        };
+        for metric in metrics {
+            let value = scene.get_value(metric);
+            let delta = delta.get_value(metric);
+            let p_value = comparison
+                .run
+                .get_stats(&scene.scene_name, metric)
+                .and_then(|stats| stats.p_value);
+            let significant = significance.is_significant(p_value);
+            changes.push(MetricChange {
+                scene_name: scene.scene_name.clone(),
+                metric: metric.clone(),
+                value,
+                baseline: value - delta,
+                delta,
+                relative_delta: relative_delta(value, delta),
+                p_value,
+                change: classify_delta(metric, value, delta, thresholds.get(metric), significant),
+            });
+        }
+    }
+    changes
+}
+
+fn change_name(change: Change) -> String {
+    match change {
+        Change::Improvement => "improvement",
+        Change::Regression => "regression",
+        Change::Neutral => "neutral",
+    }
+    .to_string()
+}
+
+/// Quotes a CSV field when it contains a separator, a quote or a line break.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::MetricsConfig;
+    use crate::matching::BoardMatch;
+    use crate::result::fixtures::{BOARD_ID, create_baseline, create_config, create_scene};
+    use crate::scene::{BenchmarkRun, SceneAliases};
+    use uuid::Uuid;
+
+    fn create_export() -> ResultExport {
+        let config = create_config(BOARD_ID, "STM32F746 Discovery", vec!["cortex-m7"]);
+        let run = BenchmarkRun::new(vec![
+            create_scene("Widgets demo", 40.0, 25.0, 10.0, 4.0, 6.0),
+            create_scene("Rectangles, rounded", 40.0, 30.0, 10.0, 4.0, 6.0),
+        ]);
+        let baseline_run = BenchmarkRun::new(vec![
+            create_scene("Widgets demo", 40.0, 20.0, 10.0, 4.0, 6.0),
+            create_scene("Old scene", 40.0, 30.0, 10.0, 4.0, 6.0),
+        ]);
+        let comparison = BoardComparison::compared(
+            config,
+            run,
+            &baseline_run,
+            BoardMatch::Id,
+            &SceneAliases::default(),
+        );
+        let baseline = create_baseline();
+        ResultExport::new(
+            &[comparison],
+            &[],
+            Some(&baseline),
+            &SceneMetric::PARSED,
+            &ThresholdConfig::default(),
+            &SignificanceConfig::default(),
+        )
+    }
+
+    #[test]
+    fn test_json_export() {
+        let export = create_export();
+        let changes = &export.boards[0].changes;
+        assert_eq!(changes.len(), SceneMetric::PARSED.len());
+        let fps = changes
+            .iter()
+            .find(|change| change.metric == SceneMetric::FPS)
+            .unwrap();
+        assert_eq!(fps.baseline, 20.0);
+        assert_eq!(fps.relative_delta, Some(25.0));
+        assert_eq!(fps.change, Change::Improvement);
+
+        let json = export.to_json().expect("Failed to serialize results");
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["schema_version"], SCHEMA_VERSION);
+        assert_eq!(value["baseline"]["sha"], "0123456789abcdef");
+        assert_eq!(value["boards"][0]["baseline_match"], "id");
+        assert_eq!(value["boards"][0]["scenes"][1]["status"], "added");
+        assert_eq!(value["boards"][0]["changes"][1]["metric"], "fps");
+        assert_eq!(value["boards"][0]["changes"][1]["change"], "improvement");
+
+        let parsed: ResultExport = serde_json::from_str(&json).expect("Failed to parse results");
+        assert_eq!(parsed, export);
+    }
+
+    #[test]
+    fn test_derived_metrics_classified_once() {
+        let config = create_config(Uuid::new_v4(), "STM32F746 Discovery", vec![]);
+        let metrics = MetricsConfig::default();
+        let mut run = BenchmarkRun::new(vec![create_scene(
+            "Widgets demo",
+            40.0,
+            25.0,
+            10.0,
+            4.0,
+            6.0,
+        )]);
+        let mut baseline_run = BenchmarkRun::new(vec![create_scene(
+            "Widgets demo",
+            40.0,
+            30.0,
+            10.0,
+            4.0,
+            6.0,
+        )]);
+        metrics.apply(&mut run).unwrap();
+        metrics.apply(&mut baseline_run).unwrap();
+        let comparison = BoardComparison::compared(
+            config,
+            run,
+            &baseline_run,
+            BoardMatch::Id,
+            &SceneAliases::default(),
+        );
+        let export = |metrics: &MetricsConfig| {
+            ResultExport::new(
+                std::slice::from_ref(&comparison),
+                &[],
+                None,
+                &metrics.classified(&[]),
+                &ThresholdConfig::default(),
+                &SignificanceConfig::default(),
+            )
+        };
+
+        // The score drops with the FPS, but is only classified when opted into
+        let export_default = export(&metrics);
+        let regressions = export_default.regressions();
+        assert_eq!(regressions.len(), 1);
+        assert_eq!(regressions[0].1.metric, SceneMetric::FPS);
+
+        let shown = MetricsConfig {
+            show: vec![SceneMetric::Score],
+            ..MetricsConfig::default()
+        };
+        let export_shown = export(&shown);
+        let metrics: Vec<&SceneMetric> = export_shown
+            .regressions()
+            .into_iter()
+            .map(|(_, change)| &change.metric)
+            .collect();
+        assert_eq!(metrics, vec![&SceneMetric::FPS, &SceneMetric::Score]);
+    }
+
+    #[test]
+    fn test_csv_export() {
+        let csv = create_export().to_csv();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], CSV_HEADER);
+        // Three scenes (compared, added and removed) with every metric
+        assert_eq!(lines.len(), 1 + 3 * SceneMetric::ALL.len());
+        assert!(lines.contains(
+            &"1,550e8400-e29b-41d4-a716-446655440000,STM32F746 Discovery,,Widgets demo,compared,fps,25,20,5,25,,improvement"
+        ));
+        assert!(lines.contains(
+            &"1,550e8400-e29b-41d4-a716-446655440000,STM32F746 Discovery,,\"Rectangles, rounded\",added,fps,30,,,,,"
+        ));
+        assert!(lines.contains(
+            &"1,550e8400-e29b-41d4-a716-446655440000,STM32F746 Discovery,,Old scene,removed,fps,,30,,,,"
+        ));
+    }
+}
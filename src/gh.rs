@@ -1,16 +1,84 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
 use octocrab::Octocrab;
 use octocrab::models::issues::Comment;
-use tracing::info;
+use octocrab::models::repos::RepoCommit;
+use octocrab::params::checks::{
+    CheckRunConclusion, CheckRunOutput, CheckRunOutputAnnotation, CheckRunOutputAnnotationLevel,
+    CheckRunStatus,
+};
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
 
 use crate::Ctx;
+use crate::check::{
+    ANNOTATION_PATH, AnnotationLevel, CheckAnnotation, CheckConclusion, CheckReport,
+};
 use crate::prelude::*;
+use crate::stats::BaselineStatistic;
+
+/// Maximum number of first-parent commits searched for a baseline result.
+pub const BASELINE_SEARCH_DEPTH: usize = 30;
+
+/// Where the search for a baseline result starts.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BaselineSource {
+    /// A commit given with `--baseline-commit`
+    Commit(String),
+    /// The merge base of a PR with its base branch
+    PullRequest(u64),
+    /// The latest commit of the baseline branch
+    Branch,
+}
+
+impl BaselineSource {
+    pub fn new(baseline_commit: Option<String>, pr_number: Option<u64>) -> Self {
+        match (baseline_commit, pr_number) {
+            (Some(commit), _) => Self::Commit(commit),
+            (None, Some(pr_number)) => Self::PullRequest(pr_number),
+            (None, None) => Self::Branch,
+        }
+    }
+}
+
+/// Commit whose results were used as the baseline.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Baseline {
+    pub sha: String,
+    /// How the commit the search started from was picked
+    pub origin: String,
+    /// Number of newer first-parent commits skipped because they had no result
+    pub skipped: usize,
+    /// Number of commits whose results were combined, starting with `sha`
+    pub combined: usize,
+    pub statistic: BaselineStatistic,
+}
 
-pub async fn get_latest_master_commit(ctx: &Ctx, octocrab: &Octocrab) -> Result<String> {
-    info!("Fetching latest master commit");
+impl fmt::Display for Baseline {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let short_sha = self.sha.get(..7).unwrap_or(&self.sha);
+        write!(f, "`{}` ({})", short_sha, self.origin)?;
+        if self.skipped > 0 {
+            write!(f, ", {} newer commit(s) had no results", self.skipped)?;
+        }
+        if self.combined > 1 {
+            write!(
+                f,
+                ", {} of its {} latest commits with results",
+                self.statistic, self.combined
+            )?;
+        }
+        Ok(())
+    }
+}
+
+pub async fn get_latest_baseline_commit(ctx: &Ctx, octocrab: &Octocrab) -> Result<String> {
+    info!("Fetching latest {} commit", ctx.baseline_branch);
     let commits = octocrab
         .repos(&ctx.gh_owner, &ctx.gh_repo)
         .list_commits()
-        .sha("master")
+        .sha(&ctx.baseline_branch)
         .per_page(1)
         .send()
         .await?;
@@ -18,30 +86,350 @@ pub async fn get_latest_master_commit(ctx: &Ctx, octocrab: &Octocrab) -> Result<
     let commit = commits
         .items
         .first()
-        .ok_or(Error::FailedToFetchMasterCommit)?;
+        .ok_or_else(|| Error::FailedToFetchBaselineCommit(ctx.baseline_branch.clone()))?;
     Ok(commit.sha.clone())
 }
+
+/// Resolves the commit the search for a baseline result starts from, and how it was picked.
+///
+/// The merge base of a PR falls back to its base SHA when the comparison isn't available.
+pub async fn get_baseline_start_commit(
+    ctx: &Ctx,
+    octocrab: &Octocrab,
+    source: &BaselineSource,
+) -> Result<(String, String)> {
+    match source {
+        BaselineSource::Commit(commit) => Ok((commit.clone(), "`--baseline-commit`".to_string())),
+        BaselineSource::PullRequest(pr_number) => {
+            info!("Fetching merge base of PR #{pr_number}");
+            let pr = octocrab
+                .pulls(&ctx.gh_owner, &ctx.gh_repo)
+                .get(*pr_number)
+                .await?;
+            let comparison = octocrab
+                .commits(&ctx.gh_owner, &ctx.gh_repo)
+                .compare(&pr.base.ref_field, &pr.head.sha)
+                .send()
+                .await;
+            match comparison {
+                Ok(comparison) => Ok((
+                    comparison.merge_base_commit.sha,
+                    format!("merge base of #{} with `{}`", pr_number, pr.base.ref_field),
+                )),
+                Err(err) => {
+                    warn!(
+                        "Failed to fetch the merge base of PR #{pr_number}, using its base SHA: {err}"
+                    );
+                    Ok((pr.base.sha, format!("base of #{}", pr_number)))
+                }
+            }
+        }
+        BaselineSource::Branch => Ok((
+            get_latest_baseline_commit(ctx, octocrab).await?,
+            format!("latest `{}` commit", ctx.baseline_branch),
+        )),
+    }
+}
+
+/// Maximum number of pages of 100 commits fetched while walking the first-parent history.
+const MAX_HISTORY_PAGES: usize = 10;
+
+/// Lists up to `depth` commits of the first-parent history of `sha`, starting with `sha`.
+///
+/// Merged branches are listed too, so pages of commits are fetched until `depth` first parents are
+/// resolved, the history ends or [`MAX_HISTORY_PAGES`] is reached.
+pub async fn get_first_parent_history(
+    ctx: &Ctx,
+    octocrab: &Octocrab,
+    sha: &str,
+    depth: usize,
+) -> Result<Vec<String>> {
+    info!("Fetching history of {sha}");
+    let mut page = octocrab
+        .repos(&ctx.gh_owner, &ctx.gh_repo)
+        .list_commits()
+        .sha(sha)
+        .per_page(100)
+        .send()
+        .await?;
+
+    let mut parents = HashMap::new();
+    let mut roots = HashSet::new();
+    let mut pages = 1;
+    loop {
+        let next = page.next.clone();
+        for commit in page.items {
+            if let Some(parent) = commit
+                .parents
+                .into_iter()
+                .next()
+                .and_then(|parent| parent.sha)
+            {
+                parents.insert(commit.sha, parent);
+            } else {
+                roots.insert(commit.sha);
+            }
+        }
+        let chain = first_parent_chain(sha, &parents, depth);
+        let last = chain.last().expect("Chain can't be empty");
+        if chain.len() >= depth || roots.contains(last) {
+            return Ok(chain);
+        }
+        if next.is_none() || pages >= MAX_HISTORY_PAGES {
+            warn!(
+                "First-parent history of {sha} truncated to {} of {depth} commits",
+                chain.len()
+            );
+            return Ok(chain);
+        }
+        match octocrab.get_page::<RepoCommit>(&next).await? {
+            Some(next_page) => page = next_page,
+            None => return Ok(chain),
+        }
+        pages += 1;
+    }
+}
+
+fn first_parent_chain(sha: &str, parents: &HashMap<String, String>, depth: usize) -> Vec<String> {
+    let mut chain = vec![sha.to_string()];
+    while chain.len() < depth {
+        let Some(parent) = parents.get(chain.last().expect("Chain can't be empty")) else {
+            break;
+        };
+        chain.push(parent.clone());
+    }
+    chain
+}
+
+/// Finds the comment of PR `pr_number` signed with `signature` by [`add_comment_signature`],
+/// searching every page of comments.
+///
+/// Only comments authored by the user of the token are considered, unless that user can't be
+/// fetched (e.g. with the `GITHUB_TOKEN` of GitHub Actions, which can't access `/user`).
 pub async fn get_pr_comment(
     ctx: &Ctx,
     octocrab: &Octocrab,
     pr_number: u64,
     signature: &str,
 ) -> Result<Option<Comment>> {
-    info!("Fetching latest PR comment for #{pr_number}");
-    let pr_comments = octocrab
+    let author = match octocrab.current().user().await {
+        Ok(user) => Some(user.login),
+        Err(err) => {
+            warn!("Failed to fetch the user of the token, matching comments of bots: {err}");
+            None
+        }
+    };
+
+    info!("Fetching PR comments of #{pr_number}");
+    let mut page = octocrab
         .issues(&ctx.gh_owner, &ctx.gh_repo)
         .list_comments(pr_number)
+        .per_page(100)
         .send()
         .await?;
-
-    Ok(pr_comments.items.into_iter().find(|comment| {
-        if let Some(body) = &comment.body {
-            body.contains(signature)
-        } else {
-            false
+    loop {
+        let next = page.next.clone();
+        let comment = page.items.into_iter().find(|comment| {
+            is_own_comment(&comment.user.login, &comment.user.r#type, author.as_deref())
+                && comment
+                    .body
+                    .as_deref()
+                    .is_some_and(|body| is_signed(body, signature))
+        });
+        if comment.is_some() {
+            return Ok(comment);
+        }
+        match octocrab.get_page::<Comment>(&next).await? {
+            Some(next_page) => page = next_page,
+            None => return Ok(None),
         }
-    }))
+    }
+}
+
+pub async fn get_pr_head_sha(ctx: &Ctx, octocrab: &Octocrab, pr_number: u64) -> Result<String> {
+    info!("Fetching head commit of PR #{pr_number}");
+    let pr = octocrab
+        .pulls(&ctx.gh_owner, &ctx.gh_repo)
+        .get(pr_number)
+        .await?;
+    Ok(pr.head.sha)
+}
+
+/// Maximum number of annotations GitHub accepts per check run request.
+const MAX_ANNOTATIONS_PER_REQUEST: usize = 50;
+
+/// Creates the completed check run `name` on `head_sha`.
+///
+/// A new check run is created on every publish, GitHub shows the latest one with a given name.
+/// Updating an existing one instead would append its annotations again. Annotations beyond the
+/// first request are appended to the new check run with further updates.
+pub async fn publish_check_run(
+    ctx: &Ctx,
+    octocrab: &Octocrab,
+    name: &str,
+    head_sha: &str,
+    check: &CheckReport,
+) -> Result<()> {
+    let checks = octocrab.checks(&ctx.gh_owner, &ctx.gh_repo);
+    let mut chunks = check.annotations.chunks(MAX_ANNOTATIONS_PER_REQUEST);
+
+    info!("Creating check run '{}' on {}", name, head_sha);
+    let id = checks
+        .create_check_run(name, head_sha)
+        .status(CheckRunStatus::Completed)
+        .conclusion(check_run_conclusion(check.conclusion))
+        .output(check_run_output(check, chunks.next().unwrap_or_default()))
+        .send()
+        .await?
+        .id;
+    for chunk in chunks {
+        checks
+            .update_check_run(id)
+            .output(check_run_output(check, chunk))
+            .send()
+            .await?;
+    }
+    Ok(())
+}
+
+fn check_run_conclusion(conclusion: CheckConclusion) -> CheckRunConclusion {
+    match conclusion {
+        CheckConclusion::Success => CheckRunConclusion::Success,
+        CheckConclusion::Failure => CheckRunConclusion::Failure,
+        CheckConclusion::Neutral => CheckRunConclusion::Neutral,
+    }
 }
+
+fn check_run_output(check: &CheckReport, annotations: &[CheckAnnotation]) -> CheckRunOutput {
+    CheckRunOutput {
+        title: check.title.clone(),
+        summary: check.summary.clone(),
+        text: None,
+        annotations: annotations
+            .iter()
+            .map(|annotation| CheckRunOutputAnnotation {
+                path: ANNOTATION_PATH.to_string(),
+                start_line: 1,
+                end_line: 1,
+                start_column: None,
+                end_column: None,
+                annotation_level: match annotation.level {
+                    AnnotationLevel::Warning => CheckRunOutputAnnotationLevel::Warning,
+                    AnnotationLevel::Failure => CheckRunOutputAnnotationLevel::Failure,
+                },
+                message: annotation.message.clone(),
+                title: Some(annotation.title.clone()),
+                raw_details: None,
+            })
+            .collect(),
+        images: vec![],
+    }
+}
+
 pub fn add_comment_signature(comment: String, signature: &str) -> String {
-    format!("<!-- {} -->\n{}", signature, comment)
+    format!("{}\n{}", signature_marker(signature), comment)
+}
+
+/// Login of the comments posted with a `GITHUB_TOKEN`.
+const GITHUB_ACTIONS_LOGIN: &str = "github-actions[bot]";
+
+fn signature_marker(signature: &str) -> String {
+    format!("<!-- {} -->", signature)
+}
+
+/// Whether a comment of `login` was posted by `author`, the user of the token.
+///
+/// Without `author` (a `GITHUB_TOKEN` can't fetch its user), only bot comments are considered.
+fn is_own_comment(login: &str, user_type: &str, author: Option<&str>) -> bool {
+    match author {
+        Some(author) => login == author,
+        None => user_type == "Bot" || login == GITHUB_ACTIONS_LOGIN,
+    }
+}
+
+/// Whether `body` starts with the exact marker of `signature`, rather than merely quoting it.
+fn is_signed(body: &str, signature: &str) -> bool {
+    body.lines().next() == Some(signature_marker(signature).as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_baseline_source() {
+        assert_eq!(
+            BaselineSource::new(Some("abc".to_string()), Some(1)),
+            BaselineSource::Commit("abc".to_string())
+        );
+        assert_eq!(
+            BaselineSource::new(None, Some(1)),
+            BaselineSource::PullRequest(1)
+        );
+        assert_eq!(BaselineSource::new(None, None), BaselineSource::Branch);
+    }
+
+    #[test]
+    fn test_first_parent_chain() {
+        // d merges c into b, the first parent of every commit comes first
+        let parents: HashMap<String, String> = [("d", "b"), ("c", "a"), ("b", "a")]
+            .into_iter()
+            .map(|(commit, parent)| (commit.to_string(), parent.to_string()))
+            .collect();
+        assert_eq!(first_parent_chain("d", &parents, 10), vec!["d", "b", "a"]);
+        assert_eq!(first_parent_chain("d", &parents, 2), vec!["d", "b"]);
+        assert_eq!(first_parent_chain("e", &parents, 10), vec!["e"]);
+    }
+
+    #[test]
+    fn test_baseline_display() {
+        let mut baseline = Baseline {
+            sha: "0123456789abcdef".to_string(),
+            origin: "merge base of #12 with `master`".to_string(),
+            skipped: 0,
+            combined: 1,
+            statistic: BaselineStatistic::Mean,
+        };
+        assert_eq!(
+            baseline.to_string(),
+            "`0123456` (merge base of #12 with `master`)"
+        );
+        baseline.skipped = 2;
+        assert_eq!(
+            baseline.to_string(),
+            "`0123456` (merge base of #12 with `master`), 2 newer commit(s) had no results"
+        );
+        baseline.combined = 5;
+        baseline.statistic = BaselineStatistic::Median;
+        assert!(
+            baseline
+                .to_string()
+                .ends_with(", median of its 5 latest commits with results")
+        );
+    }
+
+    #[test]
+    fn test_is_signed() {
+        let body = add_comment_signature("# Benchmark results".to_string(), "ejlv");
+        assert!(is_signed(&body, "ejlv"));
+        assert!(is_signed(&body.replace('\n', "\r\n"), "ejlv"));
+        assert!(!is_signed(&body, "ej"));
+        assert!(!is_signed(&body, "ejlv-other"));
+        assert!(!is_signed("> <!-- ejlv -->\nQuoted by a human", "ejlv"));
+        assert!(!is_signed("See the ejlv comment above", "ejlv"));
+    }
+
+    #[test]
+    fn test_is_own_comment() {
+        assert!(is_own_comment("ci-user", "User", Some("ci-user")));
+        assert!(!is_own_comment("someone", "User", Some("ci-user")));
+        assert!(!is_own_comment(
+            "github-actions[bot]",
+            "Bot",
+            Some("ci-user")
+        ));
+        assert!(is_own_comment("github-actions[bot]", "Bot", None));
+        assert!(is_own_comment("lvgl-bot[bot]", "Bot", None));
+        assert!(!is_own_comment("someone", "User", None));
+    }
 }
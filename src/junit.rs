@@ -0,0 +1,166 @@
+//! JUnit XML report of the budget checks, for CI test dashboards.
+
+use ej_config::ej_board_config::EjBoardConfigApi;
+
+use crate::budget::BudgetCheck;
+use crate::result::BoardComparison;
+use crate::scene::{format_percentage, format_value};
+
+/// Generates a `<testsuite>` per board config and a `<testcase>` per scene metric budget check.
+///
+/// Exceeded budgets are failures, checks against a zero baseline are skipped.
+pub fn generate_junit_report(results: &[BoardComparison], checks: &[BudgetCheck]) -> String {
+    let mut boards: Vec<&EjBoardConfigApi> = Vec::new();
+    for comparison in results {
+        if !boards.contains(&&comparison.board_config) {
+            boards.push(&comparison.board_config);
+        }
+    }
+    let failures = checks.iter().filter(|check| check.exceeded).count();
+
+    let mut report = String::new();
+    report += "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n";
+    report += &format!(
+        "<testsuites name=\"ejlv\" tests=\"{}\" failures=\"{}\">\n",
+        checks.len(),
+        failures
+    );
+    for board_config in boards {
+        let board_checks: Vec<&BudgetCheck> = checks
+            .iter()
+            .filter(|check| check.board_config == *board_config)
+            .collect();
+        report += &format_testsuite(board_config, &board_checks);
+    }
+    report += "</testsuites>\n";
+    report
+}
+
+fn format_testsuite(board_config: &EjBoardConfigApi, checks: &[&BudgetCheck]) -> String {
+    let failures = checks.iter().filter(|check| check.exceeded).count();
+    let skipped = checks.iter().filter(|check| check.change.is_none()).count();
+    let mut suite = format!(
+        "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" skipped=\"{}\">\n",
+        escape(&board_config.name),
+        checks.len(),
+        failures,
+        skipped
+    );
+    suite += "    <properties>\n";
+    suite += &format!(
+        "      <property name=\"id\" value=\"{}\"/>\n",
+        board_config.id
+    );
+    suite += &format!(
+        "      <property name=\"tags\" value=\"{}\"/>\n",
+        escape(&board_config.tags.join(","))
+    );
+    suite += "    </properties>\n";
+    for check in checks {
+        suite += &format!(
+            "    <testcase classname=\"{}\" name=\"{}\"",
+            escape(&board_config.name),
+            escape(&format!("{}: {}", check.scene_name, check.metric.label()))
+        );
+        match check.change {
+            None => {
+                suite +=
+                    ">\n      <skipped message=\"The baseline value is zero\"/>\n    </testcase>\n"
+            }
+            Some(change) if check.exceeded => {
+                let details = format!(
+                    "Measured: {}\nBaseline: {}\nChange: {}\nAllowed: {}%\nRule: {}",
+                    format_value(check.value),
+                    format_value(check.baseline),
+                    format_percentage(change),
+                    check.budget.max_regression,
+                    check.budget
+                );
+                suite += ">\n";
+                suite += &format!(
+                    "      <failure type=\"budget\" message=\"{}\">{}</failure>\n",
+                    escape(&format!(
+                        "{} changed by {}, more than the {}% allowed",
+                        check.metric.label(),
+                        format_percentage(change),
+                        check.budget.max_regression
+                    )),
+                    escape(&details)
+                );
+                suite += "    </testcase>\n";
+            }
+            Some(_) => suite += "/>\n",
+        }
+    }
+    suite += "  </testsuite>\n";
+    suite
+}
+
+/// Escapes the XML special characters of text and attribute values.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::budget::{Budget, check_budgets};
+    use crate::config::SignificanceConfig;
+    use crate::result::SceneComparison;
+    use crate::result::fixtures::{BOARD_ID, create_config, create_scene};
+    use crate::scene::{BenchmarkRun, SceneAliases, SceneMetric};
+
+    #[test]
+    fn test_generate_junit_report() {
+        let config = create_config(
+            BOARD_ID,
+            "STM32F746 Discovery",
+            vec!["embedded", "cortex-m7"],
+        );
+        // CPU 49 -> 50 (+2%), FPS 60 -> 57 (-5%), render time 0 -> 3
+        let scene = create_scene("All scenes <avg>", 50.0, 57.0, 10.0, 3.0, 5.0);
+        let mut delta = create_scene("All scenes <avg>", 50.0, -3.0, 10.0, 3.0, 5.0);
+        delta.avg_cpu = 1.0;
+        let mut comparison = BoardComparison::new(config, BenchmarkRun::new(vec![scene.clone()]));
+        comparison.scenes = vec![SceneComparison::Compared { scene, delta }];
+        let results = vec![comparison];
+        let budgets = vec![
+            Budget::new(SceneMetric::FPS, 3.0),
+            Budget::new(SceneMetric::RenderTime, 10.0),
+            Budget::new(SceneMetric::CPU, 10.0),
+        ];
+        let checks = check_budgets(
+            &results,
+            &budgets,
+            &SceneAliases::default(),
+            &SignificanceConfig::default(),
+        );
+
+        let expected = "<?xml version=\"1.0\" encoding=\"UTF-8\"?>
+<testsuites name=\"ejlv\" tests=\"3\" failures=\"1\">
+  <testsuite name=\"STM32F746 Discovery\" tests=\"3\" failures=\"1\" skipped=\"1\">
+    <properties>
+      <property name=\"id\" value=\"550e8400-e29b-41d4-a716-446655440000\"/>
+      <property name=\"tags\" value=\"embedded,cortex-m7\"/>
+    </properties>
+    <testcase classname=\"STM32F746 Discovery\" name=\"All scenes &lt;avg&gt;: CPU Usage (%)\"/>
+    <testcase classname=\"STM32F746 Discovery\" name=\"All scenes &lt;avg&gt;: FPS\">
+      <failure type=\"budget\" message=\"FPS changed by -5.0%, more than the 3% allowed\">Measured: 57
+Baseline: 60
+Change: -5.0%
+Allowed: 3%
+Rule: every scene: FPS must not drop more than 3%</failure>
+    </testcase>
+    <testcase classname=\"STM32F746 Discovery\" name=\"All scenes &lt;avg&gt;: Render Time (ms)\">
+      <skipped message=\"The baseline value is zero\"/>
+    </testcase>
+  </testsuite>
+</testsuites>
+";
+        assert_eq!(generate_junit_report(&results, &checks), expected);
+    }
+}
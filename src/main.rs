@@ -1,80 +1,164 @@
-use std::{path::PathBuf, time::Duration};
+use std::{
+    path::{Path, PathBuf},
+    process::ExitCode,
+    time::Duration,
+};
 
+use crate::budget::{BudgetCheck, check_budgets};
 use crate::chart::{COLORS, RunResult, create_comparison_chart};
-use crate::cli::{Cli, Commands, DispatchArgs};
+use crate::check::CheckReport;
+use crate::cli::{Cli, Commands, ConfigCommands};
 use crate::comment::generate_comment;
-use crate::ej::fetch_latest_run_result_from_commit;
-use crate::gh::{add_comment_signature, get_latest_master_commit, get_pr_comment};
-use crate::parser::{parse_run_result, parse_scenes};
+use crate::config::{
+    BaselineOptions, Config, DispatchJob, GithubConfig, MetricsConfig, ReportOptions,
+};
+use crate::ej::find_run_results_in_history;
+use crate::export::ResultExport;
+use crate::gh::{
+    BASELINE_SEARCH_DEPTH, Baseline, BaselineSource, add_comment_signature,
+    get_baseline_start_commit, get_first_parent_history, get_pr_comment, get_pr_head_sha,
+    publish_check_run,
+};
+use crate::junit::generate_junit_report;
+use crate::parser::{parse_benchmark_runs, parse_run_result};
 use crate::prelude::*;
-use crate::result::calculate_result_delta;
-use crate::scene::SceneMetric;
+use crate::result::{
+    BoardOutcome, aggregate_repetitions, calculate_result_delta, merge_repetitions, successful_runs,
+};
+use crate::scene::{BenchmarkRun, SceneMetric};
 use clap::Parser;
-use ej_dispatcher_sdk::{dispatch_build, dispatch_run};
+use ej_config::ej_board_config::EjBoardConfigApi;
+use ej_dispatcher_sdk::{EjRunResult, dispatch_build, dispatch_run};
+mod actions;
+mod budget;
 mod chart;
+mod check;
 mod cli;
 mod comment;
+mod config;
 mod ej;
 mod error;
+mod export;
 mod gh;
+mod junit;
+mod matching;
 mod parser;
 mod prelude;
 mod result;
 mod scene;
+mod stats;
 use octocrab::Octocrab;
 use plotters::prelude::{IntoDrawingArea, SVGBackend};
 use plotters::style::RGBColor;
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
 
 pub struct Ctx {
     pub gh_repo: String,
     pub gh_owner: String,
+    pub baseline_branch: String,
 }
 
 impl Default for Ctx {
     fn default() -> Self {
-        Self::new("lvgl", "lvgl")
+        Self::new("lvgl", "lvgl", "master")
     }
 }
 impl Ctx {
-    pub fn new(gh_repo: impl Into<String>, gh_owner: impl Into<String>) -> Self {
+    pub fn new(
+        gh_owner: impl Into<String>,
+        gh_repo: impl Into<String>,
+        baseline_branch: impl Into<String>,
+    ) -> Self {
         Self {
             gh_repo: gh_repo.into(),
             gh_owner: gh_owner.into(),
+            baseline_branch: baseline_branch.into(),
         }
     }
 }
 
+impl From<&GithubConfig> for Ctx {
+    fn from(config: &GithubConfig) -> Self {
+        Self::new(&config.owner, &config.repo, &config.baseline_branch)
+    }
+}
+
+fn read_benchmark_runs(path: &Path, metrics: &MetricsConfig) -> Result<Vec<BenchmarkRun>> {
+    let raw_results = std::fs::read_to_string(path)?;
+    let mut runs = parse_benchmark_runs(&raw_results)
+        .map_err(|diagnostic| diagnostic.with_source(path.display().to_string()))?;
+    for run in &mut runs {
+        metrics.apply(run)?;
+    }
+    Ok(runs)
+}
+
+/// Parses a run result and computes the derived metrics of its benchmark runs.
+fn parse_run_metrics(
+    result: EjRunResult,
+    metrics: &MetricsConfig,
+) -> Result<Vec<(EjBoardConfigApi, BoardOutcome)>> {
+    let mut outcomes = parse_run_result(result);
+    for (_, outcome) in &mut outcomes {
+        if let BoardOutcome::Ok(runs) = outcome {
+            for run in runs {
+                metrics.apply(run)?;
+            }
+        }
+    }
+    Ok(outcomes)
+}
+
+fn sorted_dir_entries(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut paths: Vec<PathBuf> = std::fs::read_dir(dir)?
+        .map(|dir_entry| dir_entry.expect("Invalid dir_entry").path())
+        .collect();
+
+    // So multiple runs with the same input produce the same graph
+    paths.sort();
+    Ok(paths)
+}
+
 pub fn create_benchmark_graph(
     input_dir: PathBuf,
     output: PathBuf,
     metric: SceneMetric,
+    metrics: &MetricsConfig,
     h_res: u32,
     v_res: u32,
 ) -> Result<()> {
-    let mut paths: Vec<PathBuf> = std::fs::read_dir(input_dir)?
-        .into_iter()
-        .map(|dir_entry| dir_entry.expect("Invalid dir_entry").path())
-        .collect();
-
-    // So multiple runs with the same input produce the same graph
-    paths.sort();
-
     let mut run_results = Vec::new();
-    for path in paths {
-        if !path.is_file() {
+    for path in sorted_dir_entries(&input_dir)? {
+        let runs = if path.is_dir() {
+            // Every file of a sub-directory is a repetition of the same benchmark
+            let mut repetitions = Vec::new();
+            for path in sorted_dir_entries(&path)? {
+                if path.is_file() {
+                    repetitions.push(read_benchmark_runs(&path, metrics)?);
+                }
+            }
+            aggregate_repetitions(&repetitions.iter().collect::<Vec<_>>())
+        } else if path.is_file() {
+            read_benchmark_runs(&path, metrics)?
+        } else {
             continue;
-        }
-        let raw_results = std::fs::read_to_string(&path)?;
-        let scenes = parse_scenes(&raw_results)?;
-        let run_name = path
+        };
+        let file_name = path
             .file_stem()
             .ok_or(Error::FailedToGetFileName(path.clone()))?
             .to_str()
             .ok_or(Error::FilePathConversionFailed(path.clone()))?;
 
-        let result = RunResult::new(run_name, scenes);
-        run_results.push(result);
+        let run_count = runs.len();
+        for (i, run) in runs.into_iter().enumerate() {
+            let run_name = if run_count > 1 {
+                let title = run.title().unwrap_or_else(|| (i + 1).to_string());
+                format!("{} - {}", file_name, title)
+            } else {
+                file_name.to_string()
+            };
+            run_results.push(RunResult::new(run_name, run.scenes).with_aggregates(run.aggregates));
+        }
     }
 
     let root = SVGBackend::new(&output, (1200, 800)).into_drawing_area();
@@ -93,7 +177,7 @@ pub fn create_benchmark_graph(
     root.present()?;
     Ok(())
 }
-pub async fn on_build(socket: PathBuf, job: DispatchArgs) -> Result<()> {
+pub async fn on_build(socket: PathBuf, job: DispatchJob) -> Result<()> {
     let result = dispatch_build(
         &socket,
         job.commit_hash,
@@ -116,48 +200,158 @@ pub async fn on_build(socket: PathBuf, job: DispatchArgs) -> Result<()> {
 pub async fn on_run(
     ctx: Ctx,
     socket: PathBuf,
-    job: DispatchArgs,
-    comment_path: PathBuf,
+    job: DispatchJob,
+    repetitions: usize,
+    baseline: BaselineOptions,
+    report: ReportOptions,
 ) -> Result<()> {
-    let octocrab = Octocrab::builder().build()?;
-    info!("Dispatching run");
-    let result = dispatch_run(
-        &socket,
-        job.commit_hash,
-        job.remote_url,
-        job.remote_token,
-        Duration::from_secs(job.seconds),
-    )
-    .await?;
+    if report.fail_on_regression && report.budgets.is_empty() {
+        return Err(Error::NoBudgets);
+    }
+    let mut builder = Octocrab::builder();
+    if let Some(gh_token) = baseline.gh_token.clone() {
+        builder = builder.personal_token(gh_token);
+    }
+    let octocrab = builder.build()?;
+    let mut samples = Vec::new();
+    let mut failed_run = None;
+    for repetition in 1..=repetitions {
+        info!("Dispatching run {}/{}", repetition, repetitions);
+        let result = dispatch_run(
+            &socket,
+            job.commit_hash.clone(),
+            job.remote_url.clone(),
+            job.remote_token.clone(),
+            Duration::from_secs(job.seconds),
+        )
+        .await?;
 
-    if result.success {
-        info!("Run Ok");
-    } else {
-        error!("Run Failed");
+        if result.success {
+            info!("Run Ok");
+        } else if result.results.is_empty() && result.logs.is_empty() {
+            error!("Run Failed");
+            failed_run = Some(result);
+            continue;
+        } else {
+            warn!("Run Failed, reporting the partial results");
+        }
+        debug!("Job result {}", result);
+        samples.push(result);
+    }
+    if let (true, Some(result)) = (samples.is_empty(), failed_run) {
         return Err(Error::RunError(result));
     }
-    debug!("Job result {}", result);
-    let latest_master_commit = get_latest_master_commit(&ctx, &octocrab).await?;
-    let master_result = if let Some(result) =
-        fetch_latest_run_result_from_commit(&socket, latest_master_commit).await?
-    {
-        info!("Parsing latest master result");
-        parse_run_result(result)?
-    } else {
-        Vec::new()
+
+    let (start_commit, origin) =
+        get_baseline_start_commit(&ctx, &octocrab, &baseline.source).await?;
+    let depth = BASELINE_SEARCH_DEPTH.max(baseline.window);
+    let history = get_first_parent_history(&ctx, &octocrab, &start_commit, depth).await?;
+    let found =
+        find_run_results_in_history(&socket, &history, baseline.window, repetitions).await?;
+    let BaselineOptions {
+        board_mapping,
+        scene_aliases,
+        statistic,
+        ..
+    } = baseline;
+    let baseline = match found.first() {
+        Some((skipped, _)) => Some(Baseline {
+            sha: history[*skipped].clone(),
+            origin,
+            skipped: *skipped,
+            combined: found.len(),
+            statistic,
+        }),
+        None => {
+            warn!(
+                "No baseline result found in the {} first-parent commit(s) of {}",
+                history.len(),
+                start_commit
+            );
+            None
+        }
     };
+    let baseline_results: Vec<_> = found.into_iter().flat_map(|(_, results)| results).collect();
+    info!("Parsing {} baseline result(s)", baseline_results.len());
+    let mut baseline_result = successful_runs(&merge_repetitions(
+        baseline_results
+            .into_iter()
+            .map(|result| parse_run_metrics(result, &report.metrics))
+            .collect::<Result<_>>()?,
+    ));
+    for (_, runs) in &mut baseline_result {
+        runs.iter_mut()
+            .for_each(|run| run.summarize_with(statistic));
+    }
 
     info!("Parsing latest run result");
-    let result = parse_run_result(result)?;
+    let outcomes = merge_repetitions(
+        samples
+            .into_iter()
+            .map(|result| parse_run_metrics(result, &report.metrics))
+            .collect::<Result<_>>()?,
+    );
 
     info!("Calculating result difference");
-    let result = calculate_result_delta(result, &master_result);
+    let result = calculate_result_delta(
+        successful_runs(&outcomes),
+        &baseline_result,
+        &board_mapping,
+        &scene_aliases,
+        &report.significance,
+    );
+
+    info!("Evaluating budgets");
+    let checks = check_budgets(
+        &result,
+        &report.budgets,
+        &scene_aliases,
+        &report.significance,
+    );
+    let violations: Vec<_> = checks.iter().filter_map(BudgetCheck::violation).collect();
+    for violation in &violations {
+        error!("Budget exceeded: {}", violation);
+    }
+
+    let export = ResultExport::new(
+        &result,
+        &violations,
+        baseline.as_ref(),
+        &report.metrics.classified(&report.budgets),
+        &report.thresholds,
+        &report.significance,
+    );
 
     info!("Generating comment");
-    let comment_body = generate_comment(&result);
-    tokio::fs::write(&comment_path, comment_body).await?;
-    info!("Comment available in {}", comment_path.display());
+    let comment_body = generate_comment(
+        &result,
+        &outcomes,
+        &violations,
+        baseline.as_ref(),
+        &report.thresholds,
+        &report.significance,
+        &report.metrics,
+    );
+    tokio::fs::write(&report.comment_path, &comment_body).await?;
+    info!("Comment available in {}", report.comment_path.display());
 
+    if let Some(json_out) = &report.json_out {
+        tokio::fs::write(json_out, export.to_json()?).await?;
+        info!("JSON results available in {}", json_out.display());
+    }
+    if let Some(csv_out) = &report.csv_out {
+        tokio::fs::write(csv_out, export.to_csv()).await?;
+        info!("CSV results available in {}", csv_out.display());
+    }
+    if let Some(junit_out) = &report.junit_out {
+        tokio::fs::write(junit_out, generate_junit_report(&result, &checks)).await?;
+        info!("JUnit report available in {}", junit_out.display());
+    }
+    actions::publish(&export, &comment_body, &report.comment_path).await?;
+
+    if report.fail_on_regression && !violations.is_empty() {
+        return Err(Error::BudgetExceeded(violations.len()));
+    }
     Ok(())
 }
 
@@ -190,20 +384,101 @@ pub async fn on_comment_pr(
     Ok(())
 }
 
+async fn on_publish_check(
+    ctx: Ctx,
+    comment_path: PathBuf,
+    results: PathBuf,
+    pr_number: u64,
+    head_sha: Option<String>,
+    gh_token: String,
+    name: String,
+) -> Result<()> {
+    let octocrab = Octocrab::builder().personal_token(gh_token).build()?;
+    let export: ResultExport = serde_json::from_str(&tokio::fs::read_to_string(&results).await?)?;
+    let report = tokio::fs::read_to_string(&comment_path).await?;
+    let head_sha = match head_sha {
+        Some(head_sha) => head_sha,
+        None => get_pr_head_sha(&ctx, &octocrab, pr_number).await?,
+    };
+
+    let check = CheckReport::new(&export, &report);
+    publish_check_run(&ctx, &octocrab, &name, &head_sha, &check).await
+}
+
+/// Exit code used when `--fail-on-regression` finds budget violations.
+///
+/// Differs from the codes of other failures (1) and of invalid arguments rejected by clap (2).
+const REGRESSION_EXIT_CODE: u8 = 3;
+
 #[tokio::main]
-async fn main() -> Result<()> {
+async fn main() -> ExitCode {
     tracing_subscriber::fmt::init();
-    let cli = Cli::parse();
+    match run(Cli::parse()).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            error!("{}", err);
+            ExitCode::from(exit_code(&err))
+        }
+    }
+}
+
+fn exit_code(err: &Error) -> u8 {
+    match err {
+        Error::BudgetExceeded(_) => REGRESSION_EXIT_CODE,
+        _ => 1,
+    }
+}
+
+async fn run(cli: Cli) -> Result<()> {
+    let mut config = Config::load(cli.config.as_deref())?;
+    config.github.merge(cli.gh);
+    let ctx = Ctx::from(&config.github);
 
     match cli.command {
-        Commands::DispatchBuild { socket, job } => on_build(socket, job).await,
+        Commands::DispatchBuild { socket, job } => {
+            let socket = config.dispatch.socket(socket)?;
+            let job = config.dispatch.job(job)?;
+            on_build(socket, job).await
+        }
         Commands::DispatchRun {
             socket,
             job,
             comment_path,
+            json_out,
+            csv_out,
+            junit_out,
+            fail_on_regression,
+            budgets,
+            repetitions,
+            baseline_commit,
+            baseline_window,
+            board_mapping,
+            pr_number,
+            gh_token,
         } => {
-            let ctx = Ctx::default();
-            on_run(ctx, socket, job, comment_path).await
+            let socket = config.dispatch.socket(socket)?;
+            let job = config.dispatch.job(job)?;
+            let repetitions = config.dispatch.repetitions(repetitions);
+            let baseline = BaselineOptions {
+                source: BaselineSource::new(baseline_commit, pr_number),
+                board_mapping: config.board_mapping(board_mapping)?,
+                scene_aliases: config.scene_aliases.clone(),
+                window: config.baseline.window(baseline_window),
+                statistic: config.baseline.statistic,
+                gh_token,
+            };
+            let report = ReportOptions {
+                comment_path: config.output.comment_path(comment_path)?,
+                json_out: config.output.json_out(json_out),
+                csv_out: config.output.csv_out(csv_out),
+                junit_out: config.output.junit_out(junit_out),
+                budgets: config.budgets(budgets)?,
+                thresholds: config.thresholds,
+                significance: config.significance,
+                metrics: config.metrics,
+                fail_on_regression,
+            };
+            on_run(ctx, socket, job, repetitions, baseline, report).await
         }
         Commands::CommentPR {
             comment_path,
@@ -211,15 +486,56 @@ async fn main() -> Result<()> {
             gh_token,
             signature,
         } => {
-            let ctx = Ctx::default();
+            let comment_path = config.output.comment_path(comment_path)?;
+            let signature = config.github.signature(signature)?;
             on_comment_pr(ctx, comment_path, pr_number, gh_token, signature).await
         }
+        Commands::PublishCheck {
+            comment_path,
+            results,
+            pr_number,
+            head_sha,
+            gh_token,
+            name,
+        } => {
+            let comment_path = config.output.comment_path(comment_path)?;
+            let results = config.output.results(results)?;
+            on_publish_check(
+                ctx,
+                comment_path,
+                results,
+                pr_number,
+                head_sha,
+                gh_token,
+                name,
+            )
+            .await
+        }
         Commands::BenchmarkGraph {
             input_dir,
             output,
             metric,
             h_res,
             v_res,
-        } => create_benchmark_graph(input_dir, output, metric, h_res, v_res),
+        } => create_benchmark_graph(input_dir, output, metric, &config.metrics, h_res, v_res),
+        Commands::Config { command } => match command {
+            ConfigCommands::Show => {
+                print!("{}", config.to_toml()?);
+                Ok(())
+            }
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exit_code() {
+        assert_eq!(exit_code(&Error::BudgetExceeded(1)), 3);
+        assert_eq!(exit_code(&Error::NoBudgets), 1);
+        // clap exits with 2 on invalid arguments
+        assert_ne!(REGRESSION_EXIT_CODE, 2);
     }
 }
@@ -0,0 +1,192 @@
+//! Pairing of board configs with their baseline, when their ids change.
+
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::fmt;
+use std::path::Path;
+
+use ej_config::ej_board_config::EjBoardConfigApi;
+use serde::{Deserialize, Serialize};
+use tracing::info;
+use uuid::Uuid;
+
+use crate::prelude::*;
+
+/// How a board config was paired with its baseline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BoardMatch {
+    Id,
+    /// Renamed in the board mapping file
+    Mapping,
+    Name,
+    Tags,
+}
+
+impl fmt::Display for BoardMatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BoardMatch::Id => write!(f, "id"),
+            BoardMatch::Mapping => write!(f, "board mapping"),
+            BoardMatch::Name => write!(f, "name"),
+            BoardMatch::Tags => write!(f, "tags"),
+        }
+    }
+}
+
+/// Renamed board configs, from their name in the baseline to their current name.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BoardMapping {
+    pub boards: BTreeMap<String, String>,
+}
+
+impl BoardMapping {
+    /// Loads a board mapping file.
+    pub fn load(path: &Path) -> Result<Self> {
+        info!("Loading board mapping from {}", path.display());
+        let content = std::fs::read_to_string(path)?;
+        toml::from_str(&content).map_err(|err| Error::InvalidConfig(path.to_path_buf(), err))
+    }
+
+    /// Finds the baseline of `config` by id, then by the mapping, by name and by tags.
+    ///
+    /// Baselines in `claimed` (already paired with another board) are only matched by id. Tags
+    /// only match when exactly one unclaimed baseline board has the same (non-empty) tag set.
+    pub fn find_baseline<'a, T>(
+        &self,
+        config: &EjBoardConfigApi,
+        baselines: &'a [(EjBoardConfigApi, T)],
+        claimed: &HashSet<Uuid>,
+    ) -> Option<(&'a (EjBoardConfigApi, T), BoardMatch)> {
+        if let Some(baseline) = baselines
+            .iter()
+            .find(|(baseline, _)| baseline.id == config.id)
+        {
+            return Some((baseline, BoardMatch::Id));
+        }
+        let unclaimed = || {
+            baselines
+                .iter()
+                .filter(|(baseline, _)| !claimed.contains(&baseline.id))
+        };
+        let find = |predicate: &dyn Fn(&EjBoardConfigApi) -> bool| {
+            unclaimed().find(|(baseline, _)| predicate(baseline))
+        };
+        let renamed =
+            |baseline: &EjBoardConfigApi| self.boards.get(&baseline.name) == Some(&config.name);
+        if let Some(baseline) = find(&renamed) {
+            return Some((baseline, BoardMatch::Mapping));
+        }
+        if let Some(baseline) = find(&|baseline| baseline.name == config.name) {
+            return Some((baseline, BoardMatch::Name));
+        }
+
+        let tags: BTreeSet<&String> = config.tags.iter().collect();
+        if tags.is_empty() {
+            return None;
+        }
+        let mut same_tags = unclaimed()
+            .filter(|(baseline, _)| baseline.tags.iter().collect::<BTreeSet<_>>() == tags);
+        match (same_tags.next(), same_tags.next()) {
+            (Some(baseline), None) => Some((baseline, BoardMatch::Tags)),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::result::fixtures::create_config;
+
+    #[test]
+    fn test_find_baseline() {
+        let baselines = vec![
+            (create_config(Uuid::new_v4(), "Board A", vec!["arm"]), 1),
+            (
+                create_config(Uuid::new_v4(), "Board B", vec!["x86", "linux"]),
+                2,
+            ),
+            (create_config(Uuid::new_v4(), "Board C", vec!["esp32"]), 3),
+            (create_config(Uuid::new_v4(), "Board D", vec!["esp32"]), 4),
+        ];
+        let mapping = BoardMapping {
+            boards: BTreeMap::from([("Board A".to_string(), "Board A v2".to_string())]),
+        };
+        let find = |config: &EjBoardConfigApi| {
+            mapping
+                .find_baseline(config, &baselines, &HashSet::new())
+                .map(|((_, value), strategy)| (*value, strategy))
+        };
+
+        assert_eq!(find(&baselines[2].0), Some((3, BoardMatch::Id)));
+        assert_eq!(
+            find(&create_config(Uuid::new_v4(), "Board A v2", vec![])),
+            Some((1, BoardMatch::Mapping))
+        );
+        assert_eq!(
+            find(&create_config(Uuid::new_v4(), "Board C", vec![])),
+            Some((3, BoardMatch::Name))
+        );
+        assert_eq!(
+            find(&create_config(
+                Uuid::new_v4(),
+                "Board E",
+                vec!["linux", "x86"]
+            )),
+            Some((2, BoardMatch::Tags))
+        );
+        // Ambiguous or missing tags don't match
+        assert_eq!(
+            find(&create_config(Uuid::new_v4(), "Board F", vec!["esp32"])),
+            None
+        );
+        assert_eq!(
+            find(&create_config(Uuid::new_v4(), "Board G", vec![])),
+            None
+        );
+    }
+
+    #[test]
+    fn test_find_baseline_claimed() {
+        let baselines = vec![
+            (create_config(Uuid::new_v4(), "Board A", vec!["arm"]), 1),
+            (create_config(Uuid::new_v4(), "Board B", vec!["esp32"]), 2),
+        ];
+        let mapping = BoardMapping::default();
+        let mut claimed = HashSet::new();
+
+        // Both new boards would match "Board A", by name and by tags
+        let renamed = create_config(Uuid::new_v4(), "Board A", vec![]);
+        let retagged = create_config(Uuid::new_v4(), "Board A2", vec!["arm"]);
+        let ((baseline, _), strategy) = mapping
+            .find_baseline(&renamed, &baselines, &claimed)
+            .expect("Failed to find baseline");
+        assert_eq!(strategy, BoardMatch::Name);
+        claimed.insert(baseline.id);
+        assert!(
+            mapping
+                .find_baseline(&retagged, &baselines, &claimed)
+                .is_none()
+        );
+
+        // Matching by id ignores the claimed baselines
+        assert_eq!(
+            mapping
+                .find_baseline(&baselines[0].0, &baselines, &claimed)
+                .map(|((_, value), strategy)| (*value, strategy)),
+            Some((1, BoardMatch::Id))
+        );
+    }
+
+    #[test]
+    fn test_parse_board_mapping() {
+        let mapping: BoardMapping =
+            toml::from_str("[boards]\n\"STM32F746 Disco\" = \"STM32F746 Discovery\"\n")
+                .expect("Failed to parse board mapping");
+        assert_eq!(
+            mapping.boards.get("STM32F746 Disco"),
+            Some(&"STM32F746 Discovery".to_string())
+        );
+    }
+}
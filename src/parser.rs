@@ -1,81 +1,397 @@
-use std::num::ParseIntError;
+use std::{
+    collections::BTreeMap,
+    fmt,
+    iter::{Enumerate, Peekable},
+    num::ParseFloatError,
+    str::Lines,
+};
 
 use ej_config::ej_board_config::EjBoardConfigApi;
 use ej_dispatcher_sdk::EjRunResult;
-use tracing::info;
+use tracing::{error, info, warn};
 
-use crate::{prelude::*, scene::Scene};
+use crate::{
+    result::BoardOutcome,
+    scene::{BenchmarkRun, DerivedMetrics, Scene, SceneMetric, normalize_column_name},
+};
 
-pub fn parse_run_result(result: EjRunResult) -> Result<Vec<(EjBoardConfigApi, Vec<Scene>)>> {
-    let mut results = Vec::new();
-    for (board_config, result) in result.results {
-        if result == "Skip" {
+/// Why a line of the benchmark output couldn't be parsed.
+#[derive(thiserror::Error, Debug, Clone)]
+pub enum ParseErrorKind {
+    #[error("invalid unit '{0}'")]
+    InvalidUnit(String),
+
+    #[error("invalid value '{0}': {1}")]
+    InvalidValue(String, ParseFloatError),
+
+    #[error("missing required column '{0}' in benchmark summary header")]
+    MissingColumn(String),
+}
+
+/// A parse error pointing at the offending text in the raw benchmark output.
+#[derive(Debug, Clone)]
+pub struct ParseDiagnostic {
+    pub kind: ParseErrorKind,
+    /// Board config (or file) the output belongs to
+    pub source_name: Option<String>,
+    /// 1-based line number within the raw output
+    pub line: usize,
+    /// 1-based column of the offending text
+    pub column: usize,
+    pub width: usize,
+    pub source_line: String,
+}
+
+impl ParseDiagnostic {
+    fn new(kind: ParseErrorKind, line_num: usize, line: &str, offset: usize, width: usize) -> Self {
+        Self {
+            kind,
+            source_name: None,
+            line: line_num + 1,
+            column: line[..offset].chars().count() + 1,
+            width: line[offset..offset + width].chars().count().max(1),
+            source_line: line.to_string(),
+        }
+    }
+
+    pub fn with_source(mut self, source_name: impl Into<String>) -> Self {
+        self.source_name = Some(source_name.into());
+        self
+    }
+
+    /// The offending line with the problematic text underlined.
+    pub fn snippet(&self) -> String {
+        let line_num = self.line.to_string();
+        let gutter = " ".repeat(line_num.len());
+        format!(
+            "{gutter} |\n{line_num} | {}\n{gutter} | {}{}",
+            self.source_line,
+            " ".repeat(self.column - 1),
+            "^".repeat(self.width)
+        )
+    }
+}
+
+impl fmt::Display for ParseDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(source_name) = &self.source_name {
+            write!(f, "{}: ", source_name)?;
+        }
+        write!(
+            f,
+            "line {}, column {}: {}\n{}",
+            self.line,
+            self.column,
+            self.kind,
+            self.snippet()
+        )
+    }
+}
+
+impl std::error::Error for ParseDiagnostic {}
+
+type ParseResult<T> = core::result::Result<T, ParseDiagnostic>;
+
+/// A line of a `Benchmark Summary` table split into its columns.
+struct Row<'a> {
+    line_num: usize,
+    line: &'a str,
+    /// Byte offset and text of every column
+    cols: Vec<(usize, &'a str)>,
+}
+
+impl<'a> Row<'a> {
+    fn new(line_num: usize, line: &'a str) -> Self {
+        let mut offset = 0;
+        let cols = line
+            .split(',')
+            .map(|col| {
+                let start = offset;
+                offset += col.len() + 1;
+                (start, col)
+            })
+            .collect();
+        Self {
+            line_num,
+            line,
+            cols,
+        }
+    }
+
+    fn text(&self, col_num: usize) -> &'a str {
+        self.cols[col_num].1.trim()
+    }
+
+    /// Builds a diagnostic underlining `text`, which must be a slice of the column `col_num`.
+    fn error(&self, kind: ParseErrorKind, col_num: usize, text: &str) -> ParseDiagnostic {
+        let (offset, col) = self.cols[col_num];
+        let text_offset = text.as_ptr() as usize - col.as_ptr() as usize;
+        ParseDiagnostic::new(
+            kind,
+            self.line_num,
+            self.line,
+            offset + text_offset,
+            text.len(),
+        )
+    }
+
+    fn value(&self, col_num: usize) -> ParseResult<f64> {
+        let col = self.text(col_num);
+        let value = col.trim_end_matches(|c: char| c.is_ascii_alphabetic() || c == '%');
+        let unit = &col[value.len()..];
+        if !unit.is_empty()
+            && !UNIT_SUFFIXES
+                .iter()
+                .any(|suffix| suffix.eq_ignore_ascii_case(unit))
+        {
+            return Err(self.error(ParseErrorKind::InvalidUnit(unit.to_string()), col_num, unit));
+        }
+        let value = value.trim();
+        value.parse().map_err(|err: ParseFloatError| {
+            self.error(
+                ParseErrorKind::InvalidValue(col.to_string(), err),
+                col_num,
+                col,
+            )
+        })
+    }
+}
+
+/// Column positions of a `Benchmark Summary` table, taken from its header line.
+struct ColumnMap {
+    name: usize,
+    metrics: Vec<(SceneMetric, usize)>,
+    extra: Vec<(String, usize)>,
+    len: usize,
+}
+
+impl ColumnMap {
+    fn from_header(header: &Row) -> ParseResult<Self> {
+        let mut name = None;
+        let mut metrics = Vec::new();
+        let mut extra = Vec::new();
+        for i in 0..header.cols.len() {
+            let col = header.text(i);
+            if matches!(
+                normalize_column_name(col).as_str(),
+                "name" | "scene" | "scenename"
+            ) {
+                name = Some(i);
+            } else if let Some(metric) = SceneMetric::from_column_name(col) {
+                metrics.push((metric, i));
+            } else {
+                extra.push((col.to_string(), i));
+            }
+        }
+
+        let missing_column = |column: &str| {
+            let text = header.line.trim();
+            let offset = text.as_ptr() as usize - header.line.as_ptr() as usize;
+            ParseDiagnostic::new(
+                ParseErrorKind::MissingColumn(column.to_string()),
+                header.line_num,
+                header.line,
+                offset,
+                text.len(),
+            )
+        };
+        let name = name.ok_or_else(|| missing_column("Name"))?;
+        for metric in SceneMetric::PARSED {
+            if !metrics.iter().any(|(m, _)| *m == metric) {
+                return Err(missing_column(metric.column_name()));
+            }
+        }
+        Ok(Self {
+            name,
+            metrics,
+            extra,
+            len: header.cols.len(),
+        })
+    }
+
+    fn metric(&self, metric: SceneMetric) -> usize {
+        self.metrics
+            .iter()
+            .find(|(m, _)| *m == metric)
+            .map(|(_, i)| *i)
+            .expect("Required columns are validated in from_header")
+    }
+}
+
+/// Number of log lines kept for boards that failed to run.
+const FAILURE_LOG_LINES: usize = 20;
+
+/// Computes the outcome of every board config that took part in the run.
+///
+/// A board that fails never affects the other boards: boards without results are classified
+/// from their logs and a board whose output can't be parsed is reported as
+/// [`BoardOutcome::ParseFailed`].
+pub fn parse_run_result(result: EjRunResult) -> Vec<(EjBoardConfigApi, BoardOutcome)> {
+    let mut outcomes = Vec::new();
+    for (board_config, output) in &result.results {
+        let logs = board_logs(&result, board_config);
+        let outcome = if output == "Skip" {
             info!("Skipping results for board config '{}'", board_config.name);
+            BoardOutcome::Skipped
+        } else {
+            match parse_benchmark_runs(output) {
+                Ok(runs) if !runs.is_empty() => BoardOutcome::Ok(runs),
+                Ok(_) => classify_failure(logs.unwrap_or(output)),
+                Err(diagnostic) => {
+                    let diagnostic = diagnostic.with_source(&board_config.name);
+                    error!("Failed to parse results\n{}", diagnostic);
+                    BoardOutcome::ParseFailed(diagnostic)
+                }
+            }
+        };
+        outcomes.push((board_config.clone(), outcome));
+    }
+    for (board_config, logs) in &result.logs {
+        if outcomes.iter().any(|(config, _)| config == board_config) {
             continue;
         }
-        results.push((board_config, parse_scenes(&result)?));
+        outcomes.push((board_config.clone(), classify_failure(logs)));
+    }
+    for (board_config, outcome) in &outcomes {
+        if matches!(outcome, BoardOutcome::RunFailed(_) | BoardOutcome::TimedOut) {
+            warn!("Board config '{}': {}", board_config.name, outcome);
+        }
     }
-    Ok(results)
+    outcomes
 }
 
-pub fn parse_scenes(result: &str) -> Result<Vec<Scene>> {
-    let mut found_start_of_results = false;
-    let mut found_header = false;
-    let mut scenes = Vec::new();
+fn board_logs<'a>(result: &'a EjRunResult, board_config: &EjBoardConfigApi) -> Option<&'a str> {
+    result
+        .logs
+        .iter()
+        .find(|(config, _)| config == board_config)
+        .map(|(_, logs)| logs.as_str())
+}
+
+/// Start of the line the dispatcher logs when it cancels a job that ran out of time.
+const TIMEOUT_MESSAGE: &str = "job timed out";
+
+/// Classifies a board that didn't produce any benchmark results from its logs.
+///
+/// The dispatcher doesn't report timeouts per board, so only its own timeout message counts,
+/// the firmware logging `timeout` (e.g. `SPI timeout, retrying`) is still a run failure.
+fn classify_failure(logs: &str) -> BoardOutcome {
+    let timed_out = logs.lines().any(|line| {
+        line.trim_start()
+            .get(..TIMEOUT_MESSAGE.len())
+            .is_some_and(|start| start.eq_ignore_ascii_case(TIMEOUT_MESSAGE))
+    });
+    if timed_out {
+        return BoardOutcome::TimedOut;
+    }
+    let lines: Vec<&str> = logs.lines().collect();
+    let tail = lines[lines.len().saturating_sub(FAILURE_LOG_LINES)..].join("\n");
+    BoardOutcome::RunFailed(tail)
+}
+
+/// Parses every `Benchmark Summary` block in a benchmark output.
+///
+/// The `Key: value` lines printed directly before a block (e.g. `Resolution: 800x480`,
+/// `Color depth: 16` or `Label: SDL`) are attached to that block as metadata. Any other line
+/// discards the metadata collected so far, so log lines such as `Flashing: done` printed earlier
+/// aren't mistaken for metadata.
+pub fn parse_benchmark_runs(result: &str) -> ParseResult<Vec<BenchmarkRun>> {
+    let mut runs = Vec::new();
+    let mut metadata = Vec::new();
+    let mut lines = result.lines().enumerate().peekable();
 
-    for mut line in result.lines() {
-        if line.starts_with("Benchmark Summary") {
-            found_start_of_results = true;
+    while let Some((_, line)) = lines.next() {
+        if let Some(summary) = line.strip_prefix("Benchmark Summary") {
+            let version = summary
+                .trim()
+                .strip_prefix('(')
+                .and_then(|version| version.strip_suffix(')'))
+                .map(|version| version.trim().to_string());
+            let mut run = BenchmarkRun::new(parse_scenes(&mut lines)?);
+            run.version = version;
+            run.metadata = std::mem::take(&mut metadata);
+            runs.push(run);
             continue;
         }
-        if !found_start_of_results {
-            continue;
+        if let Some((key, value)) = parse_metadata_line(line) {
+            metadata.push((key.to_string(), value.to_string()));
+        } else {
+            metadata.clear();
         }
-        line = line.trim();
-        let mut cols: Vec<&str> = line.split(",").collect();
-        if cols.len() < 6 {
+    }
+
+    Ok(runs)
+}
+
+/// Parses a `Key: value` line, ignoring log lines that merely contain a colon.
+fn parse_metadata_line(line: &str) -> Option<(&str, &str)> {
+    let (key, value) = line.trim().split_once(':')?;
+    let (key, value) = (key.trim(), value.trim());
+    let is_key = !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, ' ' | '_' | '-' | '.'));
+    (is_key && !value.is_empty()).then_some((key, value))
+}
+
+/// Parses the table following a `Benchmark Summary` line.
+///
+/// Stops before the first line that doesn't belong to the table.
+fn parse_scenes(lines: &mut Peekable<Enumerate<Lines>>) -> ParseResult<Vec<Scene>> {
+    let mut columns: Option<ColumnMap> = None;
+    let mut scenes = Vec::new();
+
+    while let Some(&(line_num, line)) = lines.peek() {
+        if line.trim().is_empty() {
             break;
         }
-        if !found_header {
-            found_header = true;
+        let row = Row::new(line_num, line);
+        let Some(columns) = &columns else {
+            columns = Some(ColumnMap::from_header(&row)?);
+            lines.next();
             continue;
+        };
+        if row.cols.len() < columns.len {
+            break;
         }
-        let line_num = scenes.len();
-        cols[1] = cols[1].strip_suffix('%').ok_or(Error::InvalidResultColumn(
-            line_num,
-            1,
-            cols[1].to_string(),
-        ))?;
+        lines.next();
 
-        let avg_cpu: i32 = parse_int_col(&cols, line_num, 1)?;
-        let avg_fps: i32 = parse_int_col(&cols, line_num, 2)?;
-        let avg_time: i32 = parse_int_col(&cols, line_num, 3)?;
-        let render_time: i32 = parse_int_col(&cols, line_num, 4)?;
-        let flush_time: i32 = parse_int_col(&cols, line_num, 5)?;
+        let mut extra_metrics = BTreeMap::new();
+        for (name, col_num) in &columns.extra {
+            match row.value(*col_num) {
+                Ok(value) => {
+                    extra_metrics.insert(name.clone(), value);
+                }
+                Err(err) => warn!("Ignoring column '{name}': {err}"),
+            }
+        }
 
         let scene = Scene {
-            scene_name: cols[0].to_string(),
-            avg_cpu,
-            avg_fps,
-            avg_time,
-            render_time,
-            flush_time,
+            scene_name: row.text(columns.name).to_string(),
+            avg_cpu: row.value(columns.metric(SceneMetric::CPU))?,
+            avg_fps: row.value(columns.metric(SceneMetric::FPS))?,
+            avg_time: row.value(columns.metric(SceneMetric::AvgTime))?,
+            render_time: row.value(columns.metric(SceneMetric::RenderTime))?,
+            flush_time: row.value(columns.metric(SceneMetric::FlushTime))?,
+            extra_metrics,
+            derived: DerivedMetrics::default(),
         };
         scenes.push(scene);
     }
 
-    return Ok(scenes);
+    Ok(scenes)
 }
 
-fn parse_int_col(cols: &Vec<&str>, line_num: usize, col_num: usize) -> Result<i32> {
-    cols[col_num].trim().parse().map_err(|err: ParseIntError| {
-        Error::ParseIntFailed(line_num, col_num, cols[col_num].to_string(), err)
-    })
-}
+/// Unit suffixes the benchmark may append to a value (`35%`, `3.25 ms`, `60 fps`).
+const UNIT_SUFFIXES: [&str; 3] = ["%", "ms", "fps"];
 
 #[cfg(test)]
 mod tests {
-    use crate::parser::{Scene, parse_scenes};
+    use std::collections::BTreeMap;
+
+    use uuid::Uuid;
+
+    use super::*;
 
     #[test]
     fn parse_results() {
@@ -101,145 +417,437 @@ Widgets demo, 32%, 61, 11, 2, 9
 All scenes avg.,64%, 60, 9, 2, 7
 ";
 
-        let actual = parse_scenes(results).expect("Failed to parse result");
+        let actual = parse_benchmark_runs(results)
+            .expect("Failed to parse result")
+            .remove(0)
+            .scenes;
         let expected = vec![
             Scene {
                 scene_name: "Empty screen".to_string(),
-                avg_cpu: 35,
-                avg_fps: 56,
-                avg_time: 4,
-                render_time: 0,
-                flush_time: 4,
+                avg_cpu: 35.0,
+                avg_fps: 56.0,
+                avg_time: 4.0,
+                render_time: 0.0,
+                flush_time: 4.0,
+                extra_metrics: BTreeMap::new(),
+                derived: DerivedMetrics::default(),
             },
             Scene {
                 scene_name: "Moving wallpaper".to_string(),
-                avg_cpu: 43,
-                avg_fps: 61,
-                avg_time: 6,
-                render_time: 0,
-                flush_time: 6,
+                avg_cpu: 43.0,
+                avg_fps: 61.0,
+                avg_time: 6.0,
+                render_time: 0.0,
+                flush_time: 6.0,
+                extra_metrics: BTreeMap::new(),
+                derived: DerivedMetrics::default(),
             },
             Scene {
                 scene_name: "Single rectangle".to_string(),
-                avg_cpu: 55,
-                avg_fps: 61,
-                avg_time: 8,
-                render_time: 0,
-                flush_time: 8,
+                avg_cpu: 55.0,
+                avg_fps: 61.0,
+                avg_time: 8.0,
+                render_time: 0.0,
+                flush_time: 8.0,
+                extra_metrics: BTreeMap::new(),
+                derived: DerivedMetrics::default(),
             },
             Scene {
                 scene_name: "Multiple rectangles".to_string(),
-                avg_cpu: 57,
-                avg_fps: 61,
-                avg_time: 8,
-                render_time: 0,
-                flush_time: 8,
+                avg_cpu: 57.0,
+                avg_fps: 61.0,
+                avg_time: 8.0,
+                render_time: 0.0,
+                flush_time: 8.0,
+                extra_metrics: BTreeMap::new(),
+                derived: DerivedMetrics::default(),
             },
             Scene {
                 scene_name: "Multiple RGB images".to_string(),
-                avg_cpu: 60,
-                avg_fps: 61,
-                avg_time: 8,
-                render_time: 0,
-                flush_time: 8,
+                avg_cpu: 60.0,
+                avg_fps: 61.0,
+                avg_time: 8.0,
+                render_time: 0.0,
+                flush_time: 8.0,
+                extra_metrics: BTreeMap::new(),
+                derived: DerivedMetrics::default(),
             },
             Scene {
                 scene_name: "Multiple ARGB images".to_string(),
-                avg_cpu: 74,
-                avg_fps: 61,
-                avg_time: 11,
-                render_time: 3,
-                flush_time: 8,
+                avg_cpu: 74.0,
+                avg_fps: 61.0,
+                avg_time: 11.0,
+                render_time: 3.0,
+                flush_time: 8.0,
+                extra_metrics: BTreeMap::new(),
+                derived: DerivedMetrics::default(),
             },
             Scene {
                 scene_name: "Rotated ARGB images".to_string(),
-                avg_cpu: 76,
-                avg_fps: 61,
-                avg_time: 11,
-                render_time: 3,
-                flush_time: 8,
+                avg_cpu: 76.0,
+                avg_fps: 61.0,
+                avg_time: 11.0,
+                render_time: 3.0,
+                flush_time: 8.0,
+                extra_metrics: BTreeMap::new(),
+                derived: DerivedMetrics::default(),
             },
             Scene {
                 scene_name: "Multiple labels".to_string(),
-                avg_cpu: 72,
-                avg_fps: 61,
-                avg_time: 10,
-                render_time: 2,
-                flush_time: 8,
+                avg_cpu: 72.0,
+                avg_fps: 61.0,
+                avg_time: 10.0,
+                render_time: 2.0,
+                flush_time: 8.0,
+                extra_metrics: BTreeMap::new(),
+                derived: DerivedMetrics::default(),
             },
             Scene {
                 scene_name: "Screen sized text".to_string(),
-                avg_cpu: 80,
-                avg_fps: 60,
-                avg_time: 14,
-                render_time: 5,
-                flush_time: 9,
+                avg_cpu: 80.0,
+                avg_fps: 60.0,
+                avg_time: 14.0,
+                render_time: 5.0,
+                flush_time: 9.0,
+                extra_metrics: BTreeMap::new(),
+                derived: DerivedMetrics::default(),
             },
             Scene {
                 scene_name: "Multiple arcs".to_string(),
-                avg_cpu: 72,
-                avg_fps: 61,
-                avg_time: 9,
-                render_time: 1,
-                flush_time: 8,
+                avg_cpu: 72.0,
+                avg_fps: 61.0,
+                avg_time: 9.0,
+                render_time: 1.0,
+                flush_time: 8.0,
+                extra_metrics: BTreeMap::new(),
+                derived: DerivedMetrics::default(),
             },
             Scene {
                 scene_name: "Containers".to_string(),
-                avg_cpu: 63,
-                avg_fps: 61,
-                avg_time: 9,
-                render_time: 1,
-                flush_time: 8,
+                avg_cpu: 63.0,
+                avg_fps: 61.0,
+                avg_time: 9.0,
+                render_time: 1.0,
+                flush_time: 8.0,
+                extra_metrics: BTreeMap::new(),
+                derived: DerivedMetrics::default(),
             },
             Scene {
                 scene_name: "Containers with overlay".to_string(),
-                avg_cpu: 88,
-                avg_fps: 58,
-                avg_time: 14,
-                render_time: 7,
-                flush_time: 7,
+                avg_cpu: 88.0,
+                avg_fps: 58.0,
+                avg_time: 14.0,
+                render_time: 7.0,
+                flush_time: 7.0,
+                extra_metrics: BTreeMap::new(),
+                derived: DerivedMetrics::default(),
             },
             Scene {
                 scene_name: "Containers with opa".to_string(),
-                avg_cpu: 71,
-                avg_fps: 61,
-                avg_time: 9,
-                render_time: 1,
-                flush_time: 8,
+                avg_cpu: 71.0,
+                avg_fps: 61.0,
+                avg_time: 9.0,
+                render_time: 1.0,
+                flush_time: 8.0,
+                extra_metrics: BTreeMap::new(),
+                derived: DerivedMetrics::default(),
             },
             Scene {
                 scene_name: "Containers with opa_layer".to_string(),
-                avg_cpu: 76,
-                avg_fps: 61,
-                avg_time: 11,
-                render_time: 3,
-                flush_time: 8,
+                avg_cpu: 76.0,
+                avg_fps: 61.0,
+                avg_time: 11.0,
+                render_time: 3.0,
+                flush_time: 8.0,
+                extra_metrics: BTreeMap::new(),
+                derived: DerivedMetrics::default(),
             },
             Scene {
                 scene_name: "Containers with scrolling".to_string(),
-                avg_cpu: 75,
-                avg_fps: 61,
-                avg_time: 11,
-                render_time: 4,
-                flush_time: 7,
+                avg_cpu: 75.0,
+                avg_fps: 61.0,
+                avg_time: 11.0,
+                render_time: 4.0,
+                flush_time: 7.0,
+                extra_metrics: BTreeMap::new(),
+                derived: DerivedMetrics::default(),
             },
             Scene {
                 scene_name: "Widgets demo".to_string(),
-                avg_cpu: 32,
-                avg_fps: 61,
-                avg_time: 11,
-                render_time: 2,
-                flush_time: 9,
+                avg_cpu: 32.0,
+                avg_fps: 61.0,
+                avg_time: 11.0,
+                render_time: 2.0,
+                flush_time: 9.0,
+                extra_metrics: BTreeMap::new(),
+                derived: DerivedMetrics::default(),
             },
             Scene {
                 scene_name: "All scenes avg.".to_string(),
-                avg_cpu: 64,
-                avg_fps: 60,
-                avg_time: 9,
-                render_time: 2,
-                flush_time: 7,
+                avg_cpu: 64.0,
+                avg_fps: 60.0,
+                avg_time: 9.0,
+                render_time: 2.0,
+                flush_time: 7.0,
+                extra_metrics: BTreeMap::new(),
+                derived: DerivedMetrics::default(),
             },
         ];
         assert_eq!(actual, expected);
     }
+
+    #[test]
+    fn parse_fractional_results_with_units() {
+        let results = "
+Benchmark Summary (9.4.0 dev)
+Name, Avg. CPU, Avg. FPS, Avg. time, render time, flush time
+Empty screen, 12.5%, 60.25 fps, 3.25 ms, 0.75 ms, 2.5ms
+All scenes avg., 35 %, 61, 4, 0.5, 3.5
+";
+
+        let actual = parse_benchmark_runs(results)
+            .expect("Failed to parse result")
+            .remove(0)
+            .scenes;
+        let expected = vec![
+            Scene {
+                scene_name: "Empty screen".to_string(),
+                avg_cpu: 12.5,
+                avg_fps: 60.25,
+                avg_time: 3.25,
+                render_time: 0.75,
+                flush_time: 2.5,
+                extra_metrics: BTreeMap::new(),
+                derived: DerivedMetrics::default(),
+            },
+            Scene {
+                scene_name: "All scenes avg.".to_string(),
+                avg_cpu: 35.0,
+                avg_fps: 61.0,
+                avg_time: 4.0,
+                render_time: 0.5,
+                flush_time: 3.5,
+                extra_metrics: BTreeMap::new(),
+                derived: DerivedMetrics::default(),
+            },
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn parse_results_unknown_unit() {
+        let results = "
+Benchmark Summary (9.4.0 dev)
+Name, Avg. CPU, Avg. FPS, Avg. time, render time, flush time
+Empty screen, 35%, 56, 4 kg, 0, 4
+";
+
+        let err = parse_benchmark_runs(results).unwrap_err();
+        assert!(matches!(err.kind, ParseErrorKind::InvalidUnit(ref unit) if unit == "kg"));
+        assert_eq!(err.line, 4);
+        assert_eq!(err.column, 26);
+        assert_eq!(
+            err.to_string(),
+            "line 4, column 26: invalid unit 'kg'\n  |\n4 | Empty screen, 35%, 56, 4 kg, 0, 4\n  |                          ^^"
+        );
+    }
+
+    #[test]
+    fn parse_results_reordered_header() {
+        let results = "
+Benchmark Summary (9.4.0 dev)
+Name, Avg. FPS, Flush Time, Avg.CPU, AVG. TIME, Render time, Draw calls
+Empty screen, 56, 4, 35%, 4, 0, 12
+";
+
+        let actual = parse_benchmark_runs(results)
+            .expect("Failed to parse result")
+            .remove(0)
+            .scenes;
+        let expected = vec![Scene {
+            scene_name: "Empty screen".to_string(),
+            avg_cpu: 35.0,
+            avg_fps: 56.0,
+            avg_time: 4.0,
+            render_time: 0.0,
+            flush_time: 4.0,
+            extra_metrics: BTreeMap::from([("Draw calls".to_string(), 12.0)]),
+            derived: DerivedMetrics::default(),
+        }];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn parse_results_missing_column() {
+        let results = "
+Benchmark Summary (9.4.0 dev)
+Name, Avg. CPU, Avg. FPS, Avg. time, flush time
+Empty screen, 35%, 56, 4, 4
+";
+
+        let err = parse_benchmark_runs(results).unwrap_err();
+        assert!(
+            matches!(err.kind, ParseErrorKind::MissingColumn(ref column) if column == "render time")
+        );
+        assert_eq!(err.line, 3);
+        assert_eq!(err.column, 1);
+    }
+
+    #[test]
+    fn parse_multiple_benchmark_runs() {
+        let results = "
+[Info] lv_init: begin
+Resolution: 800x480
+Color Depth: 16
+Label: SW renderer
+Benchmark Summary (9.4.0 dev)
+Name, Avg. CPU, Avg. FPS, Avg. time, render time, flush time
+Empty screen, 35%, 56, 4, 0, 4
+All scenes avg., 64%, 60, 9, 2, 7
+Resolution: 320x240
+Benchmark Summary (9.4.0 dev)
+Name, Avg. CPU, Avg. FPS, Avg. time, render time, flush time
+Empty screen, 20%, 61, 2, 0, 2
+
+Benchmark Summary
+Name, Avg. CPU, Avg. FPS, Avg. time, render time, flush time
+Empty screen, 10%, 61, 1, 0, 1
+";
+
+        let runs = parse_benchmark_runs(results).expect("Failed to parse result");
+        assert_eq!(runs.len(), 3);
+
+        assert_eq!(runs[0].version.as_deref(), Some("9.4.0 dev"));
+        assert_eq!(runs[0].label(), Some("SW renderer"));
+        assert_eq!(runs[0].resolution(), Some("800x480"));
+        assert_eq!(runs[0].get_metadata("color depth"), Some("16"));
+        assert_eq!(runs[0].scenes.len(), 2);
+
+        assert_eq!(runs[1].label(), None);
+        assert_eq!(runs[1].resolution(), Some("320x240"));
+        assert_eq!(runs[1].metadata.len(), 1);
+        assert_eq!(runs[1].scenes.len(), 1);
+        assert_eq!(runs[1].scenes[0].avg_cpu, 20.0);
+
+        assert_eq!(runs[2].version, None);
+        assert!(runs[2].metadata.is_empty());
+        assert_eq!(runs[2].scenes[0].avg_cpu, 10.0);
+    }
+
+    #[test]
+    fn parse_results_invalid_value_in_second_block() {
+        let results = "
+Benchmark Summary (9.4.0 dev)
+Name, Avg. CPU, Avg. FPS, Avg. time, render time, flush time
+Empty screen, 35%, 56, 4, 0, 4
+
+Benchmark Summary (9.4.0 dev)
+Name, Avg. CPU, Avg. FPS, Avg. time, render time, flush time
+Empty screen, 35%, 5x6, 4, 0, 4
+";
+
+        let err = parse_benchmark_runs(results)
+            .unwrap_err()
+            .with_source("STM32F746 Discovery");
+        assert!(matches!(err.kind, ParseErrorKind::InvalidValue(ref value, _) if value == "5x6"));
+        assert_eq!(err.line, 8);
+        assert_eq!(err.column, 20);
+        assert!(
+            err.to_string()
+                .starts_with("STM32F746 Discovery: line 8, column 20: invalid value '5x6'")
+        );
+        assert!(
+            err.to_string()
+                .ends_with("8 | Empty screen, 35%, 5x6, 4, 0, 4\n  |                    ^^^")
+        );
+    }
+
+    #[test]
+    fn parse_run_result_partial() {
+        let config = |name: &str| EjBoardConfigApi {
+            id: Uuid::new_v4(),
+            name: name.to_string(),
+            tags: Vec::new(),
+        };
+        let (ok, skipped, invalid, failed, timed_out) = (
+            config("ok"),
+            config("skipped"),
+            config("invalid"),
+            config("failed"),
+            config("timed out"),
+        );
+        let header =
+            "Benchmark Summary\nName, Avg. CPU, Avg. FPS, Avg. time, render time, flush time\n";
+        let result = EjRunResult {
+            logs: vec![
+                (failed.clone(), "Flashing...\nHard fault".to_string()),
+                (timed_out.clone(), "Job timed out after 600s".to_string()),
+            ],
+            results: vec![
+                (
+                    ok.clone(),
+                    format!("{}Empty screen, 35%, 56, 4, 0, 4\n", header),
+                ),
+                (skipped.clone(), "Skip".to_string()),
+                (
+                    invalid.clone(),
+                    format!("{}Empty screen, 35%, 5x6, 4, 0, 4\n", header),
+                ),
+                (failed.clone(), String::new()),
+            ],
+            success: false,
+        };
+
+        let outcomes = parse_run_result(result);
+        assert_eq!(outcomes.len(), 5);
+        assert!(matches!(outcomes[0], (ref c, BoardOutcome::Ok(_)) if *c == ok));
+        assert!(matches!(outcomes[1], (ref c, BoardOutcome::Skipped) if *c == skipped));
+        assert!(matches!(outcomes[2], (ref c, BoardOutcome::ParseFailed(_)) if *c == invalid));
+        assert!(
+            matches!(outcomes[3], (ref c, BoardOutcome::RunFailed(ref logs)) if *c == failed && logs == "Flashing...\nHard fault")
+        );
+        assert!(matches!(outcomes[4], (ref c, BoardOutcome::TimedOut) if *c == timed_out));
+    }
+
+    #[test]
+    fn classify_failure_timeout_message() {
+        assert!(matches!(
+            classify_failure("Flashing...\nJob timed out after 600s"),
+            BoardOutcome::TimedOut
+        ));
+        assert!(matches!(
+            classify_failure("job timed out"),
+            BoardOutcome::TimedOut
+        ));
+        let logs = "lv_timer: timeout=5ms\nSPI timeout, retrying\nHard fault";
+        assert!(
+            matches!(classify_failure(logs), BoardOutcome::RunFailed(ref tail) if tail == logs)
+        );
+        assert!(matches!(
+            classify_failure("Watchdog: timed out waiting for the display"),
+            BoardOutcome::RunFailed(_)
+        ));
+    }
+
+    #[test]
+    fn parse_metadata_directly_before_block() {
+        let results = "
+Flashing: done
+Info: starting
+[Info] lv_init: begin
+Error: retry
+lv_timer_handler: running
+
+Resolution: 800x480
+Benchmark Summary
+Name, Avg. CPU, Avg. FPS, Avg. time, render time, flush time
+Empty screen, 35%, 56, 4, 0, 4
+";
+
+        let runs = parse_benchmark_runs(results).expect("Failed to parse result");
+        assert_eq!(
+            runs[0].metadata,
+            vec![("Resolution".to_string(), "800x480".to_string())]
+        );
+    }
 }
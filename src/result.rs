@@ -1,64 +1,478 @@
+use std::collections::HashSet;
+use std::fmt;
+
 use ej_config::ej_board_config::EjBoardConfigApi;
-use tracing::warn;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::config::SignificanceConfig;
+use crate::matching::{BoardMapping, BoardMatch};
+use crate::parser::ParseDiagnostic;
+use crate::scene::{
+    BenchmarkRun, MetricDirection, Scene, SceneAliases, SceneMetric, is_zero_delta,
+    normalize_scene_name,
+};
+
+/// What happened to a single board config during a run.
+#[derive(Debug, Clone)]
+pub enum BoardOutcome {
+    Ok(Vec<BenchmarkRun>),
+    Skipped,
+    ParseFailed(ParseDiagnostic),
+    /// The board didn't produce any benchmark results, holds the end of its logs
+    RunFailed(String),
+    TimedOut,
+}
+
+impl BoardOutcome {
+    pub fn is_ok(&self) -> bool {
+        matches!(self, BoardOutcome::Ok(_))
+    }
+}
+
+impl fmt::Display for BoardOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BoardOutcome::Ok(_) => write!(f, "Ok"),
+            BoardOutcome::Skipped => write!(f, "Skipped"),
+            BoardOutcome::ParseFailed(_) => write!(f, "Failed to parse results"),
+            BoardOutcome::RunFailed(_) => write!(f, "Run failed"),
+            BoardOutcome::TimedOut => write!(f, "Timed out"),
+        }
+    }
+}
+
+/// Change of `value` relative to its baseline (`value - delta`), in percent.
+///
+/// Returns `None` when the baseline is zero, which is also the case for scenes without a baseline.
+pub fn relative_delta(value: f64, delta: f64) -> Option<f64> {
+    let baseline = value - delta;
+    (baseline != 0.0).then(|| delta / baseline.abs() * 100.0)
+}
+
+/// Whether a delta is too small to be meaningful given a relative `threshold` in percent.
+///
+/// Deltas without a relative value are never considered noise.
+pub fn is_noise(value: f64, delta: f64, threshold: f64) -> bool {
+    relative_delta(value, delta).is_some_and(|relative| relative.abs() < threshold)
+}
+
+/// How a metric delta affects performance.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Change {
+    Improvement,
+    Regression,
+    Neutral,
+}
+
+/// Classifies the delta of `metric`.
+///
+/// Deltas that are zero, noise or not statistically `significant` are [`Change::Neutral`].
+pub fn classify_delta(
+    metric: &SceneMetric,
+    value: f64,
+    delta: f64,
+    threshold: f64,
+    significant: bool,
+) -> Change {
+    if !significant || is_zero_delta(delta) || is_noise(value, delta, threshold) {
+        return Change::Neutral;
+    }
+    match (metric.direction(), delta > 0.0) {
+        (MetricDirection::HigherIsBetter, true) | (MetricDirection::LowerIsBetter, false) => {
+            Change::Improvement
+        }
+        _ => Change::Regression,
+    }
+}
+
+/// Keeps the benchmark runs of the boards whose output was parsed successfully.
+pub fn successful_runs(
+    outcomes: &[(EjBoardConfigApi, BoardOutcome)],
+) -> Vec<(EjBoardConfigApi, Vec<BenchmarkRun>)> {
+    outcomes
+        .iter()
+        .filter_map(|(config, outcome)| match outcome {
+            BoardOutcome::Ok(runs) => Some((config.clone(), runs.clone())),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Combines the outcomes of repeated runs of the same commit.
+///
+/// The runs of a board are aggregated over every repetition where it succeeded, a board that
+/// never succeeded keeps the outcome of its first repetition.
+pub fn merge_repetitions(
+    repetitions: Vec<Vec<(EjBoardConfigApi, BoardOutcome)>>,
+) -> Vec<(EjBoardConfigApi, BoardOutcome)> {
+    let mut merged: Vec<(EjBoardConfigApi, Vec<BoardOutcome>)> = Vec::new();
+    for (config, outcome) in repetitions.into_iter().flatten() {
+        match merged
+            .iter_mut()
+            .find(|(merged_config, _)| *merged_config == config)
+        {
+            Some((_, outcomes)) => outcomes.push(outcome),
+            None => merged.push((config, vec![outcome])),
+        }
+    }
+
+    merged
+        .into_iter()
+        .map(|(config, outcomes)| {
+            let samples: Vec<&Vec<BenchmarkRun>> = outcomes
+                .iter()
+                .filter_map(|outcome| match outcome {
+                    BoardOutcome::Ok(runs) => Some(runs),
+                    _ => None,
+                })
+                .collect();
+            if samples.is_empty() {
+                let outcome = outcomes.into_iter().next().unwrap_or(BoardOutcome::Skipped);
+                return (config, outcome);
+            }
+            if samples.len() < outcomes.len() {
+                warn!(
+                    "Board config '{}' succeeded in {} of {} repetitions",
+                    config.name,
+                    samples.len(),
+                    outcomes.len()
+                );
+            }
+            (config, BoardOutcome::Ok(aggregate_repetitions(&samples)))
+        })
+        .collect()
+}
+
+/// Aggregates the benchmark runs of several repetitions of the same output.
+///
+/// Runs are paired like [`find_previous_run`] does, using the first repetition as reference.
+pub fn aggregate_repetitions(samples: &[&Vec<BenchmarkRun>]) -> Vec<BenchmarkRun> {
+    let Some(reference) = samples.first() else {
+        return Vec::new();
+    };
+    (0..reference.len())
+        .map(|i| {
+            let repetitions: Vec<BenchmarkRun> = samples
+                .iter()
+                .filter_map(|runs| find_previous_run(reference, i, runs))
+                .cloned()
+                .collect();
+            BenchmarkRun::aggregate(&repetitions)
+        })
+        .collect()
+}
+
+/// A scene of a benchmark run and how it compares with the baseline run.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum SceneComparison {
+    /// The scene is in both runs, `delta` holds the new values minus the baseline ones
+    Compared { scene: Scene, delta: Scene },
+    /// The scene isn't in the baseline run
+    Added(Scene),
+    /// The scene is only in the baseline run, holds its baseline values
+    Removed(Scene),
+    /// The board has no baseline run
+    NoBaseline(Scene),
+}
+
+impl SceneComparison {
+    /// The new values of the scene, or the baseline ones of a removed scene.
+    pub fn scene(&self) -> &Scene {
+        match self {
+            SceneComparison::Compared { scene, .. }
+            | SceneComparison::Added(scene)
+            | SceneComparison::Removed(scene)
+            | SceneComparison::NoBaseline(scene) => scene,
+        }
+    }
+
+    pub fn scene_name(&self) -> &str {
+        &self.scene().scene_name
+    }
 
-use crate::scene::Scene;
+    pub fn delta(&self) -> Option<&Scene> {
+        match self {
+            SceneComparison::Compared { delta, .. } => Some(delta),
+            _ => None,
+        }
+    }
+
+    /// The `status` of the scene in the exported results.
+    pub fn status(&self) -> &'static str {
+        match self {
+            SceneComparison::Compared { .. } => "compared",
+            SceneComparison::Added(_) => "added",
+            SceneComparison::Removed(_) => "removed",
+            SceneComparison::NoBaseline(_) => "no_baseline",
+        }
+    }
+}
+
+/// A benchmark run of a board config compared with the matching baseline run.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BoardComparison {
+    pub board_config: EjBoardConfigApi,
+    pub run: BenchmarkRun,
+    /// How the board config was paired with the baseline, `None` without a baseline run
+    pub baseline_match: Option<BoardMatch>,
+    /// Differences with the metadata of the baseline run
+    pub metadata_mismatches: Vec<String>,
+    pub scenes: Vec<SceneComparison>,
+}
+
+impl BoardComparison {
+    /// A run without a baseline.
+    pub fn new(board_config: EjBoardConfigApi, run: BenchmarkRun) -> Self {
+        let scenes = run
+            .scenes
+            .iter()
+            .cloned()
+            .map(SceneComparison::NoBaseline)
+            .collect();
+        Self {
+            board_config,
+            run,
+            baseline_match: None,
+            metadata_mismatches: Vec::new(),
+            scenes,
+        }
+    }
+
+    /// A run compared with its `baseline` run.
+    pub fn compared(
+        board_config: EjBoardConfigApi,
+        run: BenchmarkRun,
+        baseline: &BenchmarkRun,
+        baseline_match: BoardMatch,
+        aliases: &SceneAliases,
+    ) -> Self {
+        Self {
+            metadata_mismatches: run.compare_metadata(baseline),
+            scenes: calculate_delta(&run.scenes, &baseline.scenes, aliases),
+            board_config,
+            run,
+            baseline_match: Some(baseline_match),
+        }
+    }
+
+    /// Finds a scene by its normalized name, see [`normalize_scene_name`].
+    pub fn find_scene(&self, scene_name: &str) -> Option<&SceneComparison> {
+        let scene_name = normalize_scene_name(scene_name);
+        self.scenes
+            .iter()
+            .rfind(|scene| normalize_scene_name(scene.scene_name()) == scene_name)
+    }
+}
 
+/// Compares every benchmark run with the matching previous run.
+///
+/// Returns one entry per benchmark run, so a board appears once per `Benchmark Summary` block.
+///
+/// Board configs are paired with their baseline through `mapping` (see
+/// [`BoardMapping::find_baseline`]) and scenes by their normalized name or `aliases`. When both
+/// runs aggregate repetitions, the p-value of every metric is computed with `significance`.
 pub fn calculate_result_delta(
-    new_results: Vec<(EjBoardConfigApi, Vec<Scene>)>,
-    previous_results: &Vec<(EjBoardConfigApi, Vec<Scene>)>,
-) -> Vec<(EjBoardConfigApi, Vec<Scene>, Vec<Scene>)> {
+    new_results: Vec<(EjBoardConfigApi, Vec<BenchmarkRun>)>,
+    previous_results: &[(EjBoardConfigApi, Vec<BenchmarkRun>)],
+    mapping: &BoardMapping,
+    aliases: &SceneAliases,
+    significance: &SignificanceConfig,
+) -> Vec<BoardComparison> {
     let mut result = Vec::new();
-    for (new_config, new_result) in new_results.into_iter() {
-        if let Some((_, prev_result)) = previous_results
-            .iter()
-            .find(|(prev_board_config, _)| prev_board_config.id == new_config.id)
-        {
-            let delta = calculate_delta(&new_result, prev_result);
-            result.push((new_config, new_result, delta));
-        } else {
-            result.push((new_config, new_result.clone(), new_result));
+    // Baselines of boards whose id didn't change are claimed first, so renamed boards can't take them
+    let mut claimed: HashSet<Uuid> = previous_results
+        .iter()
+        .map(|(prev_config, _)| prev_config.id)
+        .filter(|id| {
+            new_results
+                .iter()
+                .any(|(new_config, _)| new_config.id == *id)
+        })
+        .collect();
+    for (new_config, new_runs) in new_results.into_iter() {
+        let (prev_runs, baseline_match) = match mapping.find_baseline(
+            &new_config,
+            previous_results,
+            &claimed,
+        ) {
+            Some(((prev_config, prev_runs), strategy)) => {
+                if strategy != BoardMatch::Id {
+                    info!(
+                        "Board config '{}' matched baseline '{}' ({}) by {}",
+                        new_config.name, prev_config.name, prev_config.id, strategy
+                    );
+                    claimed.insert(prev_config.id);
+                }
+                (prev_runs.as_slice(), Some(strategy))
+            }
+            None => {
+                if mapping
+                    .find_baseline(&new_config, previous_results, &HashSet::new())
+                    .is_some()
+                {
+                    warn!(
+                        "Board config '{}' matches a baseline already paired with another board, it isn't compared",
+                        new_config.name
+                    );
+                }
+                (Default::default(), None)
+            }
+        };
+
+        for (i, new_run) in new_runs.iter().enumerate() {
+            let (Some(prev_run), Some(baseline_match)) =
+                (find_previous_run(&new_runs, i, prev_runs), baseline_match)
+            else {
+                result.push(BoardComparison::new(new_config.clone(), new_run.clone()));
+                continue;
+            };
+            let mut new_run = new_run.clone();
+            if !test_significance(&mut new_run, prev_run, aliases, significance) {
+                warn!(
+                    "Too few samples on '{}' for the significance test to reach p < {}, changes are only compared against the thresholds",
+                    new_config.name, significance.alpha
+                );
+            }
+            let comparison = BoardComparison::compared(
+                new_config.clone(),
+                new_run,
+                prev_run,
+                baseline_match,
+                aliases,
+            );
+            for mismatch in &comparison.metadata_mismatches {
+                warn!(
+                    "Baseline metadata differs for '{}': {}",
+                    new_config.name, mismatch
+                );
+            }
+            result.push(comparison);
         }
     }
     result
 }
-fn calculate_delta(a: &Vec<Scene>, b: &Vec<Scene>) -> Vec<Scene> {
+
+/// Fills the p-value of every scene metric sampled in both runs.
+///
+/// Returns `false` when some metric has too few samples for the test to reach `alpha`.
+fn test_significance(
+    run: &mut BenchmarkRun,
+    baseline: &BenchmarkRun,
+    aliases: &SceneAliases,
+    significance: &SignificanceConfig,
+) -> bool {
+    let mut reaches_alpha = true;
+    for aggregate in &mut run.aggregates {
+        let Some(baseline_aggregate) = baseline
+            .aggregates
+            .iter()
+            .find(|baseline| aliases.same_scene(&baseline.scene_name, &aggregate.scene_name))
+        else {
+            continue;
+        };
+        for metric in &SceneMetric::ALL {
+            let baseline_stats = baseline_aggregate.get(metric);
+            let stats = aggregate.get_mut(metric);
+            stats.p_value = significance.p_value(&stats.samples, &baseline_stats.samples);
+            let sampled = stats.samples.len() > 1 && baseline_stats.samples.len() > 1;
+            reaches_alpha &= !sampled
+                || significance.reaches_alpha(stats.samples.len(), baseline_stats.samples.len());
+        }
+    }
+    reaches_alpha
+}
+
+/// Finds the run of `prev_runs` produced by the same benchmark configuration as `new_runs[index]`.
+///
+/// Runs sharing a configuration are paired in the order they were printed.
+fn find_previous_run<'a>(
+    new_runs: &[BenchmarkRun],
+    index: usize,
+    prev_runs: &'a [BenchmarkRun],
+) -> Option<&'a BenchmarkRun> {
+    let new_run = &new_runs[index];
+    let occurrence = new_runs[..index]
+        .iter()
+        .filter(|run| run.same_config(new_run))
+        .count();
+    prev_runs
+        .iter()
+        .filter(|run| run.same_config(new_run))
+        .nth(occurrence)
+}
+/// Compares the scenes of a run with the ones of its baseline, matched by normalized name or
+/// `aliases`.
+///
+/// Scenes only found in the baseline are listed last.
+fn calculate_delta(a: &[Scene], b: &[Scene], aliases: &SceneAliases) -> Vec<SceneComparison> {
     let mut result = Vec::new();
     for a_scene in a.iter() {
         if let Some(b_scene) = b
             .iter()
-            .find(|b_scene| b_scene.scene_name == a_scene.scene_name)
+            .find(|b_scene| aliases.same_scene(&b_scene.scene_name, &a_scene.scene_name))
         {
-            result.push(Scene {
+            let delta = Scene {
                 scene_name: a_scene.scene_name.clone(),
                 avg_cpu: a_scene.avg_cpu - b_scene.avg_cpu,
                 avg_fps: a_scene.avg_fps - b_scene.avg_fps,
                 avg_time: a_scene.avg_time - b_scene.avg_time,
                 render_time: a_scene.render_time - b_scene.render_time,
                 flush_time: a_scene.flush_time - b_scene.flush_time,
+                extra_metrics: a_scene
+                    .extra_metrics
+                    .iter()
+                    .filter_map(|(name, a_value)| {
+                        let b_value = b_scene.extra_metrics.get(name)?;
+                        Some((name.clone(), a_value - b_value))
+                    })
+                    .collect(),
+                derived: a_scene.derived - b_scene.derived,
+            };
+            result.push(SceneComparison::Compared {
+                scene: a_scene.clone(),
+                delta,
             });
         } else {
-            warn!("Couldn't find scene '{}' in {:?}", a_scene.scene_name, b);
-            result.push(a_scene.clone());
+            warn!("Scene '{}' isn't in the baseline", a_scene.scene_name);
+            result.push(SceneComparison::Added(a_scene.clone()));
         }
     }
     for b_scene in b.iter() {
-        if let None = a
+        if !a
             .iter()
-            .find(|a_scene| a_scene.scene_name == b_scene.scene_name)
+            .any(|a_scene| aliases.same_scene(&a_scene.scene_name, &b_scene.scene_name))
         {
-            warn!("Couldn't find scene '{}' in {:?}", b_scene.scene_name, a);
-            result.push(b_scene.clone());
+            warn!("Scene '{}' was removed", b_scene.scene_name);
+            result.push(SceneComparison::Removed(b_scene.clone()));
         }
     }
     result
 }
+
+/// Factories shared by the tests of every module.
 #[cfg(test)]
-mod tests {
+pub mod fixtures {
     use super::*;
-    use uuid::Uuid;
-
-    // Helper function to create a test scene
-    fn create_scene(name: &str, cpu: i32, fps: i32, time: i32, render: i32, flush: i32) -> Scene {
+    use crate::gh::Baseline;
+    use crate::scene::DerivedMetrics;
+    use crate::stats::BaselineStatistic;
+    use std::collections::BTreeMap;
+
+    /// Id of the board configs whose id is part of the expected output.
+    pub const BOARD_ID: Uuid = Uuid::from_u128(0x550e8400_e29b_41d4_a716_446655440000);
+
+    pub fn create_scene(
+        name: &str,
+        cpu: f64,
+        fps: f64,
+        time: f64,
+        render: f64,
+        flush: f64,
+    ) -> Scene {
         Scene {
             scene_name: name.to_string(),
             avg_cpu: cpu,
@@ -66,11 +480,12 @@ mod tests {
             avg_time: time,
             render_time: render,
             flush_time: flush,
+            extra_metrics: BTreeMap::new(),
+            derived: DerivedMetrics::default(),
         }
     }
 
-    // Helper function to create a test config
-    fn create_config(id: Uuid, name: &str, tags: Vec<&str>) -> EjBoardConfigApi {
+    pub fn create_config(id: Uuid, name: &str, tags: Vec<&str>) -> EjBoardConfigApi {
         EjBoardConfigApi {
             id,
             name: name.to_string(),
@@ -78,144 +493,208 @@ mod tests {
         }
     }
 
+    /// Pairs every scene with the delta at the same position.
+    pub fn compare_scenes(scenes: Vec<Scene>, deltas: Vec<Scene>) -> Vec<SceneComparison> {
+        scenes
+            .into_iter()
+            .zip(deltas)
+            .map(|(scene, delta)| SceneComparison::Compared { scene, delta })
+            .collect()
+    }
+
+    /// Results of a single run of `Board A`, its scenes compared with `deltas`.
+    pub fn create_results(scenes: Vec<Scene>, deltas: Vec<Scene>) -> Vec<BoardComparison> {
+        let config = create_config(Uuid::new_v4(), "Board A", vec![]);
+        let mut comparison = BoardComparison::new(config, BenchmarkRun::new(scenes.clone()));
+        comparison.scenes = compare_scenes(scenes, deltas);
+        vec![comparison]
+    }
+
+    /// Results with a FPS regression of Empty screen (60 -> 57, -5%) and a render time
+    /// regression of Widgets demo (10 -> 20 ms, +100%).
+    pub fn create_regressed_results() -> Vec<BoardComparison> {
+        create_results(
+            vec![
+                create_scene("Empty screen", 0.0, 57.0, 0.0, 10.0, 0.0),
+                create_scene("Widgets demo", 0.0, 30.0, 0.0, 20.0, 0.0),
+            ],
+            vec![
+                create_scene("Empty screen", 0.0, -3.0, 0.0, 0.0, 0.0),
+                create_scene("Widgets demo", 0.0, 0.0, 0.0, 10.0, 0.0),
+            ],
+        )
+    }
+
+    pub fn create_baseline() -> Baseline {
+        Baseline {
+            sha: "0123456789abcdef".to_string(),
+            origin: "latest commit of master".to_string(),
+            skipped: 0,
+            combined: 1,
+            statistic: BaselineStatistic::Mean,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::fixtures::{create_config, create_scene};
+    use super::*;
+    use crate::stats::SignificanceTest;
+
+    fn delta(comparison: &SceneComparison) -> &Scene {
+        comparison.delta().expect("Scene wasn't compared")
+    }
+
     #[test]
     fn test_calculate_delta_identical_scenes() {
-        let scene1 = create_scene("test_scene", 50, 60, 100, 80, 20);
-        let scene2 = create_scene("test_scene", 50, 60, 100, 80, 20);
+        let scene1 = create_scene("test_scene", 50.0, 60.0, 100.0, 80.0, 20.0);
+        let scene2 = create_scene("test_scene", 50.0, 60.0, 100.0, 80.0, 20.0);
 
         let a = vec![scene1];
         let b = vec![scene2];
 
-        let result = calculate_delta(&a, &b);
+        let result = calculate_delta(&a, &b, &SceneAliases::default());
 
         assert_eq!(result.len(), 1);
-        assert_eq!(result[0].scene_name, "test_scene");
-        assert_eq!(result[0].avg_cpu, 0);
-        assert_eq!(result[0].avg_fps, 0);
-        assert_eq!(result[0].avg_time, 0);
-        assert_eq!(result[0].render_time, 0);
-        assert_eq!(result[0].flush_time, 0);
+        assert_eq!(result[0].scene_name(), "test_scene");
+        assert_eq!(delta(&result[0]).avg_cpu, 0.0);
+        assert_eq!(delta(&result[0]).avg_fps, 0.0);
+        assert_eq!(delta(&result[0]).avg_time, 0.0);
+        assert_eq!(delta(&result[0]).render_time, 0.0);
+        assert_eq!(delta(&result[0]).flush_time, 0.0);
     }
 
     #[test]
     fn test_calculate_delta_negative_values() {
-        let curr = create_scene("test_scene", 40, 55, 90, 70, 15);
-        let prev = create_scene("test_scene", 50, 60, 100, 80, 20);
+        let curr = create_scene("test_scene", 40.0, 55.0, 90.0, 70.0, 15.0);
+        let prev = create_scene("test_scene", 50.0, 60.0, 100.0, 80.0, 20.0);
 
         let curr = vec![curr];
         let prev = vec![prev];
 
-        let result = calculate_delta(&curr, &prev);
+        let result = calculate_delta(&curr, &prev, &SceneAliases::default());
 
         assert_eq!(result.len(), 1);
-        assert_eq!(result[0].scene_name, "test_scene");
-        assert_eq!(result[0].avg_cpu, -10);
-        assert_eq!(result[0].avg_fps, -5);
-        assert_eq!(result[0].avg_time, -10);
-        assert_eq!(result[0].render_time, -10);
-        assert_eq!(result[0].flush_time, -5);
+        assert_eq!(result[0].scene_name(), "test_scene");
+        assert_eq!(delta(&result[0]).avg_cpu, -10.0);
+        assert_eq!(delta(&result[0]).avg_fps, -5.0);
+        assert_eq!(delta(&result[0]).avg_time, -10.0);
+        assert_eq!(delta(&result[0]).render_time, -10.0);
+        assert_eq!(delta(&result[0]).flush_time, -5.0);
     }
 
     #[test]
     fn test_calculate_delta_different_values() {
-        let curr = create_scene("test_scene", 60, 70, 110, 90, 25);
-        let prev = create_scene("test_scene", 50, 60, 100, 80, 20);
+        let curr = create_scene("test_scene", 60.0, 70.0, 110.0, 90.0, 25.0);
+        let prev = create_scene("test_scene", 50.0, 60.0, 100.0, 80.0, 20.0);
 
         let curr = vec![curr];
         let prev = vec![prev];
 
-        let result = calculate_delta(&curr, &prev);
+        let result = calculate_delta(&curr, &prev, &SceneAliases::default());
 
         assert_eq!(result.len(), 1);
-        assert_eq!(result[0].scene_name, "test_scene");
-        assert_eq!(result[0].avg_cpu, 10);
-        assert_eq!(result[0].avg_fps, 10);
-        assert_eq!(result[0].avg_time, 10);
-        assert_eq!(result[0].render_time, 10);
-        assert_eq!(result[0].flush_time, 5);
+        assert_eq!(result[0].scene_name(), "test_scene");
+        assert_eq!(delta(&result[0]).avg_cpu, 10.0);
+        assert_eq!(delta(&result[0]).avg_fps, 10.0);
+        assert_eq!(delta(&result[0]).avg_time, 10.0);
+        assert_eq!(delta(&result[0]).render_time, 10.0);
+        assert_eq!(delta(&result[0]).flush_time, 5.0);
     }
 
     #[test]
     fn test_calculate_delta_scene_only_in_a() {
-        let scene_a = create_scene("unique_scene", 40, 55, 90, 70, 15);
-        let scene_b = create_scene("other_scene", 50, 60, 100, 80, 20);
+        let scene_a = create_scene("unique_scene", 40.0, 55.0, 90.0, 70.0, 15.0);
+        let scene_b = create_scene("other_scene", 50.0, 60.0, 100.0, 80.0, 20.0);
 
         let a = vec![scene_a.clone()];
-        let b = vec![scene_b];
-
-        let result = calculate_delta(&a, &b);
-
-        assert_eq!(result.len(), 2);
-
-        // The unique scene from 'a' should be cloned as-is
-        let unique_scene = result
-            .iter()
-            .find(|s| s.scene_name == "unique_scene")
-            .unwrap();
-        assert_eq!(unique_scene.avg_cpu, 40);
-        assert_eq!(unique_scene.avg_fps, 55);
-
-        // The scene from 'b' should be added
-        let other_scene = result
-            .iter()
-            .find(|s| s.scene_name == "other_scene")
-            .unwrap();
-        assert_eq!(other_scene.avg_cpu, 50);
-        assert_eq!(other_scene.avg_fps, 60);
+        let b = vec![scene_b.clone()];
+
+        let result = calculate_delta(&a, &b, &SceneAliases::default());
+
+        // The unique scene from 'a' was added, the one from 'b' removed
+        assert_eq!(
+            result,
+            vec![
+                SceneComparison::Added(scene_a),
+                SceneComparison::Removed(scene_b)
+            ]
+        );
     }
 
     #[test]
     fn test_calculate_delta_scene_only_in_b() {
-        let scene_a = create_scene("common_scene", 40, 55, 90, 70, 15);
-        let scene_b1 = create_scene("common_scene", 50, 60, 100, 80, 20);
-        let scene_b2 = create_scene("unique_in_b", 30, 45, 85, 65, 10);
+        let scene_a = create_scene("common_scene", 40.0, 55.0, 90.0, 70.0, 15.0);
+        let scene_b1 = create_scene("common_scene", 50.0, 60.0, 100.0, 80.0, 20.0);
+        let scene_b2 = create_scene("unique_in_b", 30.0, 45.0, 85.0, 65.0, 10.0);
 
         let a = vec![scene_a];
         let b = vec![scene_b1, scene_b2.clone()];
 
-        let result = calculate_delta(&a, &b);
+        let result = calculate_delta(&a, &b, &SceneAliases::default());
 
         assert_eq!(result.len(), 2);
 
         // The common scene should have delta calculated
-        let common_scene = result
-            .iter()
-            .find(|s| s.scene_name == "common_scene")
-            .unwrap();
-        assert_eq!(common_scene.avg_cpu, -10);
+        assert_eq!(delta(&result[0]).avg_cpu, -10.0);
 
-        // The unique scene from 'b' should be cloned as-is
-        let unique_scene = result
-            .iter()
-            .find(|s| s.scene_name == "unique_in_b")
-            .unwrap();
-        assert_eq!(unique_scene.avg_cpu, 30);
-        assert_eq!(unique_scene.avg_fps, 45);
+        // The unique scene from 'b' was removed and keeps its baseline values
+        assert_eq!(result[1], SceneComparison::Removed(scene_b2));
+        assert_eq!(result[1].delta(), None);
+    }
+
+    #[test]
+    fn test_calculate_delta_renamed_scenes() {
+        let a = vec![
+            create_scene("Multiple argb images", 40.0, 55.0, 90.0, 70.0, 15.0),
+            create_scene("All scenes avg", 40.0, 55.0, 90.0, 70.0, 15.0),
+            create_scene("Multiple opaque images", 40.0, 55.0, 90.0, 70.0, 15.0),
+        ];
+        let b = vec![
+            create_scene("Multiple ARGB images", 50.0, 60.0, 100.0, 80.0, 20.0),
+            create_scene("All scenes avg.", 50.0, 60.0, 100.0, 80.0, 20.0),
+            create_scene("Multiple RGB images", 50.0, 60.0, 100.0, 80.0, 20.0),
+        ];
+        let aliases: SceneAliases = [(
+            "Multiple RGB images".to_string(),
+            "Multiple opaque images".to_string(),
+        )]
+        .into_iter()
+        .collect();
+
+        let result = calculate_delta(&a, &b, &aliases);
+
+        assert_eq!(result.len(), 3);
+        for (comparison, scene) in result.iter().zip(&a) {
+            assert_eq!(comparison.scene_name(), scene.scene_name);
+            assert_eq!(delta(comparison).avg_cpu, -10.0);
+        }
     }
 
     #[test]
     fn test_calculate_delta_multiple_scenes() {
         let scenes_a = vec![
-            create_scene("scene1", 40, 55, 90, 70, 15),
-            create_scene("scene2", 35, 50, 85, 65, 12),
+            create_scene("scene1", 40.0, 55.0, 90.0, 70.0, 15.0),
+            create_scene("scene2", 35.0, 50.0, 85.0, 65.0, 12.0),
         ];
 
         let scenes_b = vec![
-            create_scene("scene1", 50, 60, 100, 80, 20),
-            create_scene("scene2", 45, 55, 95, 75, 18),
+            create_scene("scene1", 50.0, 60.0, 100.0, 80.0, 20.0),
+            create_scene("scene2", 45.0, 55.0, 95.0, 75.0, 18.0),
         ];
 
-        let result = calculate_delta(&scenes_a, &scenes_b);
+        let result = calculate_delta(&scenes_a, &scenes_b, &SceneAliases::default());
 
         assert_eq!(result.len(), 2);
 
-        let scene1_result = result.iter().find(|s| s.scene_name == "scene1").unwrap();
-        assert_eq!(scene1_result.avg_cpu, -10);
-        assert_eq!(scene1_result.avg_fps, -5);
+        let scene1_result = delta(result.iter().find(|s| s.scene_name() == "scene1").unwrap());
+        assert_eq!(scene1_result.avg_cpu, -10.0);
+        assert_eq!(scene1_result.avg_fps, -5.0);
 
-        let scene2_result = result.iter().find(|s| s.scene_name == "scene2").unwrap();
-        assert_eq!(scene2_result.avg_cpu, -10);
-        assert_eq!(scene2_result.avg_fps, -5);
+        let scene2_result = delta(result.iter().find(|s| s.scene_name() == "scene2").unwrap());
+        assert_eq!(scene2_result.avg_cpu, -10.0);
+        assert_eq!(scene2_result.avg_fps, -5.0);
     }
 
     #[test]
@@ -223,7 +702,7 @@ mod tests {
         let empty_a: Vec<Scene> = vec![];
         let empty_b: Vec<Scene> = vec![];
 
-        let result = calculate_delta(&empty_a, &empty_b);
+        let result = calculate_delta(&empty_a, &empty_b, &SceneAliases::default());
 
         assert_eq!(result.len(), 0);
     }
@@ -233,23 +712,29 @@ mod tests {
         let config_id = Uuid::new_v4();
         let config = create_config(config_id, "test_config", vec!["tag1"]);
 
-        let new_scene = create_scene("test_scene", 50, 60, 100, 80, 20);
-        let prev_scene = create_scene("test_scene", 40, 55, 90, 70, 15);
+        let new_scene = create_scene("test_scene", 50.0, 60.0, 100.0, 80.0, 20.0);
+        let prev_scene = create_scene("test_scene", 40.0, 55.0, 90.0, 70.0, 15.0);
 
-        let new_results = vec![(config.clone(), vec![new_scene])];
-        let previous_results = vec![(config.clone(), vec![prev_scene])];
+        let new_results = vec![(config.clone(), vec![BenchmarkRun::new(vec![new_scene])])];
+        let previous_results = vec![(config.clone(), vec![BenchmarkRun::new(vec![prev_scene])])];
 
-        let result = calculate_result_delta(new_results, &previous_results);
+        let result = calculate_result_delta(
+            new_results,
+            &previous_results,
+            &BoardMapping::default(),
+            &SceneAliases::default(),
+            &SignificanceConfig::default(),
+        );
 
         assert_eq!(result.len(), 1);
-        assert_eq!(result[0].0.id, config_id);
-        assert_eq!(result[0].1.len(), 1); // new_result
-        assert_eq!(result[0].2.len(), 1); // delta
+        assert_eq!(result[0].board_config.id, config_id);
+        assert_eq!(result[0].run.scenes.len(), 1); // new_result
+        assert_eq!(result[0].scenes.len(), 1); // delta
 
         // Check delta calculation
-        let delta_scene = &result[0].2[0];
-        assert_eq!(delta_scene.avg_cpu, 10); // 50 - 40
-        assert_eq!(delta_scene.avg_fps, 5); // 60 - 55
+        let delta_scene = delta(&result[0].scenes[0]);
+        assert_eq!(delta_scene.avg_cpu, 10.0); // 50 - 40
+        assert_eq!(delta_scene.avg_fps, 5.0); // 60 - 55
     }
 
     #[test]
@@ -257,21 +742,30 @@ mod tests {
         let config_id = Uuid::new_v4();
         let config = create_config(config_id, "new_config", vec!["tag1"]);
 
-        let new_scene = create_scene("test_scene", 50, 60, 100, 80, 20);
+        let new_scene = create_scene("test_scene", 50.0, 60.0, 100.0, 80.0, 20.0);
 
-        let new_results = vec![(config.clone(), vec![new_scene.clone()])];
+        let new_results = vec![(
+            config.clone(),
+            vec![BenchmarkRun::new(vec![new_scene.clone()])],
+        )];
         let previous_results = vec![];
 
-        let result = calculate_result_delta(new_results, &previous_results);
+        let result = calculate_result_delta(
+            new_results,
+            &previous_results,
+            &BoardMapping::default(),
+            &SceneAliases::default(),
+            &SignificanceConfig::default(),
+        );
 
         assert_eq!(result.len(), 1);
-        assert_eq!(result[0].0.id, config_id);
-        assert_eq!(result[0].1.len(), 1); // new_result
-        assert_eq!(result[0].2.len(), 1); // delta should be same as new_result
+        assert_eq!(result[0].board_config.id, config_id);
+        assert_eq!(result[0].run.scenes.len(), 1); // new_result
+        assert_eq!(result[0].scenes.len(), 1);
+        assert_eq!(result[0].baseline_match, None);
 
-        // When no previous config exists, delta should be identical to new_result
-        assert_eq!(result[0].1[0].avg_cpu, result[0].2[0].avg_cpu);
-        assert_eq!(result[0].1[0].avg_fps, result[0].2[0].avg_fps);
+        // When no previous config exists, the scenes have no baseline
+        assert_eq!(result[0].scenes[0], SceneComparison::NoBaseline(new_scene));
     }
 
     #[test]
@@ -281,20 +775,28 @@ mod tests {
         let config1 = create_config(config1_id, "config1", vec!["tag1"]);
         let config2 = create_config(config2_id, "config2", vec!["tag2"]);
 
-        let new_scene = create_scene("test_scene", 50, 60, 100, 80, 20);
-        let prev_scene = create_scene("test_scene", 40, 55, 90, 70, 15);
+        let new_scene = create_scene("test_scene", 50.0, 60.0, 100.0, 80.0, 20.0);
+        let prev_scene = create_scene("test_scene", 40.0, 55.0, 90.0, 70.0, 15.0);
 
-        let new_results = vec![(config1.clone(), vec![new_scene.clone()])];
-        let previous_results = vec![(config2.clone(), vec![prev_scene])];
+        let new_results = vec![(
+            config1.clone(),
+            vec![BenchmarkRun::new(vec![new_scene.clone()])],
+        )];
+        let previous_results = vec![(config2.clone(), vec![BenchmarkRun::new(vec![prev_scene])])];
 
-        let result = calculate_result_delta(new_results, &previous_results);
+        let result = calculate_result_delta(
+            new_results,
+            &previous_results,
+            &BoardMapping::default(),
+            &SceneAliases::default(),
+            &SignificanceConfig::default(),
+        );
 
         assert_eq!(result.len(), 1);
-        assert_eq!(result[0].0.id, config1_id);
+        assert_eq!(result[0].board_config.id, config1_id);
 
-        // Since config IDs don't match, delta should be same as new_result
-        assert_eq!(result[0].1[0].avg_cpu, result[0].2[0].avg_cpu);
-        assert_eq!(result[0].1[0].avg_fps, result[0].2[0].avg_fps);
+        // Since neither the config ids, names nor tags match, the scenes have no baseline
+        assert_eq!(result[0].scenes[0], SceneComparison::NoBaseline(new_scene));
     }
 
     #[test]
@@ -304,30 +806,42 @@ mod tests {
         let config1 = create_config(config1_id, "config1", vec!["tag1"]);
         let config2 = create_config(config2_id, "config2", vec!["tag2"]);
 
-        let new_scene1 = create_scene("scene1", 50, 60, 100, 80, 20);
-        let new_scene2 = create_scene("scene2", 45, 55, 95, 75, 18);
-        let prev_scene1 = create_scene("scene1", 40, 55, 90, 70, 15);
-        let prev_scene2 = create_scene("scene2", 35, 50, 85, 65, 12);
+        let new_scene1 = create_scene("scene1", 50.0, 60.0, 100.0, 80.0, 20.0);
+        let new_scene2 = create_scene("scene2", 45.0, 55.0, 95.0, 75.0, 18.0);
+        let prev_scene1 = create_scene("scene1", 40.0, 55.0, 90.0, 70.0, 15.0);
+        let prev_scene2 = create_scene("scene2", 35.0, 50.0, 85.0, 65.0, 12.0);
 
         let new_results = vec![
-            (config1.clone(), vec![new_scene1]),
-            (config2.clone(), vec![new_scene2]),
+            (config1.clone(), vec![BenchmarkRun::new(vec![new_scene1])]),
+            (config2.clone(), vec![BenchmarkRun::new(vec![new_scene2])]),
         ];
         let previous_results = vec![
-            (config1.clone(), vec![prev_scene1]),
-            (config2.clone(), vec![prev_scene2]),
+            (config1.clone(), vec![BenchmarkRun::new(vec![prev_scene1])]),
+            (config2.clone(), vec![BenchmarkRun::new(vec![prev_scene2])]),
         ];
 
-        let result = calculate_result_delta(new_results, &previous_results);
+        let result = calculate_result_delta(
+            new_results,
+            &previous_results,
+            &BoardMapping::default(),
+            &SceneAliases::default(),
+            &SignificanceConfig::default(),
+        );
 
         assert_eq!(result.len(), 2);
 
         // Both configs should have deltas calculated
-        let result1 = result.iter().find(|r| r.0.id == config1_id).unwrap();
-        let result2 = result.iter().find(|r| r.0.id == config2_id).unwrap();
+        let result1 = result
+            .iter()
+            .find(|r| r.board_config.id == config1_id)
+            .unwrap();
+        let result2 = result
+            .iter()
+            .find(|r| r.board_config.id == config2_id)
+            .unwrap();
 
-        assert_eq!(result1.2[0].avg_cpu, 10); // 50 - 40
-        assert_eq!(result2.2[0].avg_cpu, 10); // 45 - 35
+        assert_eq!(delta(&result1.scenes[0]).avg_cpu, 10.0); // 50 - 40
+        assert_eq!(delta(&result2.scenes[0]).avg_cpu, 10.0); // 45 - 35
     }
 
     #[test]
@@ -335,8 +849,334 @@ mod tests {
         let new_results = vec![];
         let previous_results = vec![];
 
-        let result = calculate_result_delta(new_results, &previous_results);
+        let result = calculate_result_delta(
+            new_results,
+            &previous_results,
+            &BoardMapping::default(),
+            &SceneAliases::default(),
+            &SignificanceConfig::default(),
+        );
 
         assert_eq!(result.len(), 0);
     }
+
+    #[test]
+    fn test_calculate_result_delta_multiple_runs_per_board() {
+        let config = create_config(Uuid::new_v4(), "config", vec!["tag"]);
+
+        let mut new_small =
+            BenchmarkRun::new(vec![create_scene("scene", 20.0, 60.0, 2.0, 1.0, 1.0)]);
+        new_small
+            .metadata
+            .push(("Resolution".to_string(), "320x240".to_string()));
+        let mut new_large =
+            BenchmarkRun::new(vec![create_scene("scene", 60.0, 30.0, 8.0, 5.0, 3.0)]);
+        new_large
+            .metadata
+            .push(("Resolution".to_string(), "800x480".to_string()));
+        let mut new_unknown =
+            BenchmarkRun::new(vec![create_scene("scene", 1.0, 1.0, 1.0, 1.0, 1.0)]);
+        new_unknown
+            .metadata
+            .push(("Resolution".to_string(), "1024x600".to_string()));
+
+        let mut prev_large =
+            BenchmarkRun::new(vec![create_scene("scene", 50.0, 30.0, 7.0, 4.0, 3.0)]);
+        prev_large
+            .metadata
+            .push(("Resolution".to_string(), "800x480".to_string()));
+        let mut prev_small =
+            BenchmarkRun::new(vec![create_scene("scene", 25.0, 60.0, 2.0, 1.0, 1.0)]);
+        prev_small
+            .metadata
+            .push(("Resolution".to_string(), "320x240".to_string()));
+
+        let new_results = vec![(config.clone(), vec![new_small, new_large, new_unknown])];
+        let previous_results = vec![(config.clone(), vec![prev_large, prev_small])];
+
+        let result = calculate_result_delta(
+            new_results,
+            &previous_results,
+            &BoardMapping::default(),
+            &SceneAliases::default(),
+            &SignificanceConfig::default(),
+        );
+
+        assert_eq!(result.len(), 3);
+        assert_eq!(result[0].run.resolution(), Some("320x240"));
+        assert_eq!(delta(&result[0].scenes[0]).avg_cpu, -5.0);
+        assert_eq!(result[1].run.resolution(), Some("800x480"));
+        assert_eq!(delta(&result[1].scenes[0]).avg_cpu, 10.0);
+        // No previous run with that resolution
+        assert!(matches!(
+            result[2].scenes[0],
+            SceneComparison::NoBaseline(_)
+        ));
+    }
+
+    #[test]
+    fn test_calculate_result_delta_metadata_mismatch() {
+        let config = create_config(Uuid::new_v4(), "config", vec!["tag"]);
+
+        let mut new_run = BenchmarkRun::new(vec![create_scene("scene", 20.0, 60.0, 2.0, 1.0, 1.0)]);
+        new_run.version = Some("9.4.0".to_string());
+        new_run
+            .metadata
+            .push(("Color depth".to_string(), "32".to_string()));
+        let mut prev_run =
+            BenchmarkRun::new(vec![create_scene("scene", 20.0, 60.0, 2.0, 1.0, 1.0)]);
+        prev_run.version = Some("9.3.0".to_string());
+        prev_run
+            .metadata
+            .push(("Color depth".to_string(), "16".to_string()));
+        prev_run
+            .metadata
+            .push(("Renderer".to_string(), "SW".to_string()));
+
+        let result = calculate_result_delta(
+            vec![(config.clone(), vec![new_run])],
+            &[(config, vec![prev_run])],
+            &BoardMapping::default(),
+            &SceneAliases::default(),
+            &SignificanceConfig::default(),
+        );
+
+        assert_eq!(
+            result[0].metadata_mismatches,
+            vec![
+                "LVGL version: 9.3.0 → 9.4.0".to_string(),
+                "Color depth: 16 → 32".to_string(),
+                "Renderer: SW → (none)".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_relative_delta() {
+        assert_eq!(relative_delta(60.0, 10.0), Some(20.0));
+        assert_eq!(relative_delta(40.0, -10.0), Some(-20.0));
+        assert_eq!(relative_delta(5.0, 5.0), None);
+        assert_eq!(relative_delta(0.0, 0.0), None);
+    }
+
+    #[test]
+    fn test_is_noise() {
+        assert!(is_noise(101.0, 1.0, 2.0));
+        assert!(!is_noise(103.0, 3.0, 2.0));
+        assert!(!is_noise(1.0, 0.5, 0.0));
+        assert!(!is_noise(5.0, 5.0, 100.0));
+    }
+
+    #[test]
+    fn test_classify_delta() {
+        use SceneMetric::*;
+
+        assert_eq!(
+            classify_delta(&FPS, 60.0, 5.0, 0.0, true),
+            Change::Improvement
+        );
+        assert_eq!(
+            classify_delta(&FPS, 50.0, -5.0, 0.0, true),
+            Change::Regression
+        );
+        assert_eq!(
+            classify_delta(&CPU, 60.0, 5.0, 0.0, true),
+            Change::Regression
+        );
+        assert_eq!(
+            classify_delta(&RenderTime, 5.0, -1.0, 0.0, true),
+            Change::Improvement
+        );
+        assert_eq!(
+            classify_delta(&AvgTime, 10.0, 0.0, 0.0, true),
+            Change::Neutral
+        );
+        assert_eq!(
+            classify_delta(&FlushTime, 101.0, 1.0, 2.0, true),
+            Change::Neutral
+        );
+    }
+
+    #[test]
+    fn test_merge_repetitions() {
+        let config_a = create_config(Uuid::new_v4(), "Board A", vec![]);
+        let config_b = create_config(Uuid::new_v4(), "Board B", vec![]);
+        let run = |fps: f64| {
+            BenchmarkRun::new(vec![create_scene("Empty screen", 30.0, fps, 4.0, 0.0, 4.0)])
+        };
+
+        let merged = merge_repetitions(vec![
+            vec![
+                (config_a.clone(), BoardOutcome::Ok(vec![run(56.0)])),
+                (config_b.clone(), BoardOutcome::TimedOut),
+            ],
+            vec![
+                (config_a.clone(), BoardOutcome::Ok(vec![run(60.0)])),
+                (config_b.clone(), BoardOutcome::Skipped),
+            ],
+            vec![(config_a.clone(), BoardOutcome::RunFailed(String::new()))],
+        ]);
+
+        assert_eq!(merged.len(), 2);
+        let BoardOutcome::Ok(runs) = &merged[0].1 else {
+            panic!("Expected board A to succeed");
+        };
+        assert_eq!(runs.len(), 1);
+        assert_eq!(runs[0].repetitions(), 2);
+        assert_eq!(runs[0].scenes[0].avg_fps, 58.0);
+        assert!(matches!(merged[1].1, BoardOutcome::TimedOut));
+    }
+
+    #[test]
+    fn test_calculate_result_delta_significance() {
+        let config = create_config(Uuid::new_v4(), "Board A", vec![]);
+        let runs = |samples: &[f64]| {
+            let runs: Vec<BenchmarkRun> = samples
+                .iter()
+                .map(|&fps| {
+                    BenchmarkRun::new(vec![create_scene("Empty screen", 30.0, fps, 4.0, 0.0, 4.0)])
+                })
+                .collect();
+            vec![BenchmarkRun::aggregate(&runs)]
+        };
+        let new_results = vec![(config.clone(), runs(&[55.0, 56.0, 57.0]))];
+        let previous_results = vec![(config, runs(&[60.0, 61.0, 62.0]))];
+
+        let result = calculate_result_delta(
+            new_results,
+            &previous_results,
+            &BoardMapping::default(),
+            &SceneAliases::default(),
+            &SignificanceConfig::default(),
+        );
+        let run = &result[0].run;
+        let fps = run.get_stats("Empty screen", &SceneMetric::FPS).unwrap();
+        assert!(fps.p_value.unwrap() < 0.01);
+        let cpu = run.get_stats("Empty screen", &SceneMetric::CPU).unwrap();
+        assert_eq!(cpu.p_value, Some(1.0));
+        assert_eq!(
+            classify_delta(&SceneMetric::FPS, 56.0, -5.0, 0.0, false),
+            Change::Neutral
+        );
+    }
+
+    #[test]
+    fn test_calculate_result_delta_small_samples() {
+        let config = create_config(Uuid::new_v4(), "Board A", vec![]);
+        let runs = |samples: &[f64]| {
+            let runs: Vec<BenchmarkRun> = samples
+                .iter()
+                .map(|&fps| {
+                    BenchmarkRun::new(vec![create_scene("Empty screen", 30.0, fps, 4.0, 0.0, 4.0)])
+                })
+                .collect();
+            vec![BenchmarkRun::aggregate(&runs)]
+        };
+        let significance = SignificanceConfig {
+            test: SignificanceTest::MannWhitney,
+            alpha: 0.05,
+        };
+
+        // With 3 repetitions, the Mann-Whitney U test can't go below p = 0.08
+        let result = calculate_result_delta(
+            vec![(config.clone(), runs(&[55.0, 56.0, 57.0]))],
+            &[(config.clone(), runs(&[60.0, 61.0, 62.0]))],
+            &BoardMapping::default(),
+            &SceneAliases::default(),
+            &significance,
+        );
+        let fps = result[0]
+            .run
+            .get_stats("Empty screen", &SceneMetric::FPS)
+            .unwrap();
+        assert_eq!(fps.p_value, None);
+        assert!(significance.is_significant(fps.p_value));
+
+        let result = calculate_result_delta(
+            vec![(config.clone(), runs(&[54.0, 55.0, 56.0, 57.0]))],
+            &[(config, runs(&[60.0, 61.0, 62.0, 63.0]))],
+            &BoardMapping::default(),
+            &SceneAliases::default(),
+            &significance,
+        );
+        let fps = result[0]
+            .run
+            .get_stats("Empty screen", &SceneMetric::FPS)
+            .unwrap();
+        assert!(fps.p_value.unwrap() < 0.05);
+    }
+
+    #[test]
+    fn test_calculate_result_delta_regenerated_config() {
+        let new_config = create_config(Uuid::new_v4(), "Board A", vec!["tag1"]);
+        let prev_config = create_config(Uuid::new_v4(), "Board A", vec!["tag2"]);
+
+        let new_scene = create_scene("test_scene", 50.0, 60.0, 100.0, 80.0, 20.0);
+        let prev_scene = create_scene("test_scene", 40.0, 55.0, 90.0, 70.0, 15.0);
+
+        let new_results = vec![(new_config, vec![BenchmarkRun::new(vec![new_scene])])];
+        let previous_results = vec![(prev_config, vec![BenchmarkRun::new(vec![prev_scene])])];
+
+        let result = calculate_result_delta(
+            new_results,
+            &previous_results,
+            &BoardMapping::default(),
+            &SceneAliases::default(),
+            &SignificanceConfig::default(),
+        );
+        assert_eq!(result[0].baseline_match, Some(BoardMatch::Name));
+        assert_eq!(delta(&result[0].scenes[0]).avg_cpu, 10.0);
+    }
+
+    #[test]
+    fn test_calculate_result_delta_baseline_claimed_once() {
+        let prev_config = create_config(Uuid::new_v4(), "Board A", vec!["tag1"]);
+        // Regenerated under the same name, and a new board with the same tags
+        let renamed_config = create_config(Uuid::new_v4(), "Board A", vec![]);
+        let tagged_config = create_config(Uuid::new_v4(), "Board B", vec!["tag1"]);
+
+        let scene = create_scene("test_scene", 50.0, 60.0, 100.0, 80.0, 20.0);
+        let prev_scene = create_scene("test_scene", 40.0, 55.0, 90.0, 70.0, 15.0);
+        let new_results = vec![
+            (renamed_config, vec![BenchmarkRun::new(vec![scene.clone()])]),
+            (tagged_config, vec![BenchmarkRun::new(vec![scene])]),
+        ];
+        let previous_results = vec![(prev_config, vec![BenchmarkRun::new(vec![prev_scene])])];
+
+        let result = calculate_result_delta(
+            new_results,
+            &previous_results,
+            &BoardMapping::default(),
+            &SceneAliases::default(),
+            &SignificanceConfig::default(),
+        );
+        assert_eq!(result[0].baseline_match, Some(BoardMatch::Name));
+        assert_eq!(result[1].baseline_match, None);
+    }
+
+    #[test]
+    fn test_calculate_result_delta_id_match_claimed_first() {
+        let prev_config = create_config(Uuid::new_v4(), "Board A", vec![]);
+        // A new board named like the baseline, listed before the board keeping its id
+        let other_config = create_config(Uuid::new_v4(), "Board A", vec![]);
+        let mut same_config = prev_config.clone();
+        same_config.name = "Board A v2".to_string();
+
+        let scene = create_scene("test_scene", 50.0, 60.0, 100.0, 80.0, 20.0);
+        let new_results = vec![
+            (other_config, vec![BenchmarkRun::new(vec![scene.clone()])]),
+            (same_config, vec![BenchmarkRun::new(vec![scene.clone()])]),
+        ];
+        let previous_results = vec![(prev_config, vec![BenchmarkRun::new(vec![scene])])];
+
+        let result = calculate_result_delta(
+            new_results,
+            &previous_results,
+            &BoardMapping::default(),
+            &SceneAliases::default(),
+            &SignificanceConfig::default(),
+        );
+        assert_eq!(result[0].baseline_match, None);
+        assert_eq!(result[1].baseline_match, Some(BoardMatch::Id));
+    }
 }
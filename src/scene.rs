@@ -1,39 +1,435 @@
-use std::str::FromStr;
+use std::{
+    collections::{BTreeMap, HashSet},
+    ops::Sub,
+    str::FromStr,
+};
+
+use serde::{Deserialize, Serialize};
 
 use crate::error::Error;
+use crate::stats::{BaselineStatistic, MetricStats};
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Scene {
     pub scene_name: String,
-    pub avg_cpu: i32,
-    pub avg_fps: i32,
-    pub avg_time: i32,
-    pub render_time: i32,
-    pub flush_time: i32,
+    pub avg_cpu: f64,
+    pub avg_fps: f64,
+    pub avg_time: f64,
+    pub render_time: f64,
+    pub flush_time: f64,
+    /// Columns that don't map to a [`SceneMetric`], keyed by their header name
+    pub extra_metrics: BTreeMap<String, f64>,
+    /// Metrics computed from the columns above, see [`Scene::derive_metrics`]
+    pub derived: DerivedMetrics,
+}
+
+/// Metrics that aren't printed by the benchmark but computed from the parsed columns.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct DerivedMetrics {
+    /// Part of the frame time spent rendering, in percent
+    pub render_share: f64,
+    /// Time left in the frame budget of the target FPS, in ms (negative when it's exceeded)
+    pub frame_headroom: f64,
+    /// FPS relative to the target FPS, in percent
+    pub score: f64,
+}
+
+/// The scenes of a single `Benchmark Summary` block and the metadata printed around it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BenchmarkRun {
+    /// LVGL version from the `Benchmark Summary (<version>)` line
+    pub version: Option<String>,
+    /// `Key: value` lines printed before the summary (resolution, color depth, renderer, ...)
+    pub metadata: Vec<(String, String)>,
+    pub scenes: Vec<Scene>,
+    /// Spread of every scene when the run aggregates repetitions, `scenes` then holds the means
+    pub aggregates: Vec<SceneAggregate>,
 }
 
-#[derive(Debug, Clone)]
+/// A scene over repeated runs.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SceneAggregate {
+    pub scene_name: String,
+    pub avg_cpu: MetricStats,
+    pub avg_fps: MetricStats,
+    pub avg_time: MetricStats,
+    pub render_time: MetricStats,
+    pub flush_time: MetricStats,
+    pub render_share: MetricStats,
+    pub frame_headroom: MetricStats,
+    pub score: MetricStats,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[allow(clippy::upper_case_acronyms)]
 pub enum SceneMetric {
+    #[serde(rename = "fps")]
     FPS,
+    #[serde(rename = "cpu")]
     CPU,
+    #[serde(rename = "avg_time")]
     AvgTime,
+    #[serde(rename = "render_time")]
     RenderTime,
+    #[serde(rename = "flush_time")]
     FlushTime,
+    #[serde(rename = "render_share")]
+    RenderShare,
+    #[serde(rename = "frame_headroom")]
+    FrameHeadroom,
+    #[serde(rename = "score")]
+    Score,
+}
+
+/// Whether an increase of a metric is an improvement or a regression.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MetricDirection {
+    HigherIsBetter,
+    LowerIsBetter,
 }
 
 impl Scene {
-    pub fn get_value(&self, metric: &SceneMetric) -> i32 {
+    pub fn get_value(&self, metric: &SceneMetric) -> f64 {
         match metric {
             SceneMetric::FPS => self.avg_fps,
             SceneMetric::CPU => self.avg_cpu,
             SceneMetric::AvgTime => self.avg_time,
             SceneMetric::RenderTime => self.render_time,
             SceneMetric::FlushTime => self.flush_time,
+            SceneMetric::RenderShare => self.derived.render_share,
+            SceneMetric::FrameHeadroom => self.derived.frame_headroom,
+            SceneMetric::Score => self.derived.score,
+        }
+    }
+
+    /// Computes the [`DerivedMetrics`] of the scene, the frame budget being `1000 / target_fps` ms.
+    pub fn derive_metrics(&mut self, target_fps: f64) {
+        let render_share = if self.avg_time > 0.0 {
+            self.render_time / self.avg_time * 100.0
+        } else {
+            0.0
+        };
+        self.derived = DerivedMetrics {
+            render_share,
+            frame_headroom: 1000.0 / target_fps - self.avg_time,
+            score: self.avg_fps / target_fps * 100.0,
+        };
+    }
+}
+
+impl Sub for DerivedMetrics {
+    type Output = DerivedMetrics;
+
+    fn sub(self, other: DerivedMetrics) -> DerivedMetrics {
+        DerivedMetrics {
+            render_share: self.render_share - other.render_share,
+            frame_headroom: self.frame_headroom - other.frame_headroom,
+            score: self.score - other.score,
         }
     }
 }
 
+impl SceneAggregate {
+    pub fn get(&self, metric: &SceneMetric) -> &MetricStats {
+        match metric {
+            SceneMetric::FPS => &self.avg_fps,
+            SceneMetric::CPU => &self.avg_cpu,
+            SceneMetric::AvgTime => &self.avg_time,
+            SceneMetric::RenderTime => &self.render_time,
+            SceneMetric::FlushTime => &self.flush_time,
+            SceneMetric::RenderShare => &self.render_share,
+            SceneMetric::FrameHeadroom => &self.frame_headroom,
+            SceneMetric::Score => &self.score,
+        }
+    }
+
+    pub fn get_mut(&mut self, metric: &SceneMetric) -> &mut MetricStats {
+        match metric {
+            SceneMetric::FPS => &mut self.avg_fps,
+            SceneMetric::CPU => &mut self.avg_cpu,
+            SceneMetric::AvgTime => &mut self.avg_time,
+            SceneMetric::RenderTime => &mut self.render_time,
+            SceneMetric::FlushTime => &mut self.flush_time,
+            SceneMetric::RenderShare => &mut self.render_share,
+            SceneMetric::FrameHeadroom => &mut self.frame_headroom,
+            SceneMetric::Score => &mut self.score,
+        }
+    }
+}
+
+impl BenchmarkRun {
+    pub fn new(scenes: Vec<Scene>) -> Self {
+        Self {
+            version: None,
+            metadata: Vec::new(),
+            scenes,
+            aggregates: Vec::new(),
+        }
+    }
+
+    /// Combines repetitions of the same benchmark run.
+    ///
+    /// The scenes of the result hold the mean of every metric and `aggregates` their spread.
+    /// Scenes missing from some repetitions are aggregated over the repetitions that have them.
+    /// A single run is returned as is.
+    pub fn aggregate(runs: &[BenchmarkRun]) -> BenchmarkRun {
+        let Some((first, others)) = runs.split_first() else {
+            return BenchmarkRun::new(Vec::new());
+        };
+        if others.is_empty() {
+            return first.clone();
+        }
+
+        let mut aggregated = first.clone();
+        for scene in others.iter().flat_map(|run| &run.scenes) {
+            if !aggregated
+                .scenes
+                .iter()
+                .any(|aggregated| aggregated.scene_name == scene.scene_name)
+            {
+                aggregated.scenes.push(scene.clone());
+            }
+        }
+        aggregated.aggregates = aggregated
+            .scenes
+            .iter()
+            .map(|scene| {
+                let samples: Vec<&Scene> = runs
+                    .iter()
+                    .filter_map(|run| {
+                        run.scenes
+                            .iter()
+                            .find(|sample| sample.scene_name == scene.scene_name)
+                    })
+                    .collect();
+                let stats = |metric: SceneMetric| {
+                    MetricStats::from_samples(
+                        samples
+                            .iter()
+                            .map(|sample| sample.get_value(&metric))
+                            .collect(),
+                    )
+                };
+                SceneAggregate {
+                    scene_name: scene.scene_name.clone(),
+                    avg_cpu: stats(SceneMetric::CPU),
+                    avg_fps: stats(SceneMetric::FPS),
+                    avg_time: stats(SceneMetric::AvgTime),
+                    render_time: stats(SceneMetric::RenderTime),
+                    flush_time: stats(SceneMetric::FlushTime),
+                    render_share: stats(SceneMetric::RenderShare),
+                    frame_headroom: stats(SceneMetric::FrameHeadroom),
+                    score: stats(SceneMetric::Score),
+                }
+            })
+            .collect();
+        aggregated.summarize_with(BaselineStatistic::Mean);
+        aggregated
+    }
+
+    /// Replaces the value of every aggregated scene metric with `statistic` of its samples.
+    pub fn summarize_with(&mut self, statistic: BaselineStatistic) {
+        for (scene, aggregate) in self.scenes.iter_mut().zip(&self.aggregates) {
+            scene.avg_cpu = statistic.of(&aggregate.avg_cpu);
+            scene.avg_fps = statistic.of(&aggregate.avg_fps);
+            scene.avg_time = statistic.of(&aggregate.avg_time);
+            scene.render_time = statistic.of(&aggregate.render_time);
+            scene.flush_time = statistic.of(&aggregate.flush_time);
+            scene.derived = DerivedMetrics {
+                render_share: statistic.of(&aggregate.render_share),
+                frame_headroom: statistic.of(&aggregate.frame_headroom),
+                score: statistic.of(&aggregate.score),
+            };
+        }
+    }
+
+    /// Looks up the spread of a scene metric, if the run aggregates repetitions.
+    pub fn get_stats(&self, scene_name: &str, metric: &SceneMetric) -> Option<&MetricStats> {
+        self.aggregates
+            .iter()
+            .find(|aggregate| aggregate.scene_name == scene_name)
+            .map(|aggregate| aggregate.get(metric))
+    }
+
+    /// Number of repetitions aggregated in this run.
+    pub fn repetitions(&self) -> usize {
+        self.aggregates
+            .iter()
+            .map(|aggregate| aggregate.avg_fps.samples.len())
+            .max()
+            .unwrap_or(1)
+    }
+
+    /// Appends a [`WEIGHTED_SCORE_SCENE`] holding the weighted mean of every metric of the scenes
+    /// listed in `weights`, by normalized name.
+    ///
+    /// Nothing is added when none of the weighted scenes are in the run.
+    pub fn add_weighted_scene(&mut self, weights: &BTreeMap<String, f64>) {
+        let weighted: Vec<(&Scene, f64)> = self
+            .scenes
+            .iter()
+            .filter_map(|scene| {
+                let name = normalize_scene_name(&scene.scene_name);
+                weights
+                    .iter()
+                    .find(|(weighted, _)| normalize_scene_name(weighted) == name)
+                    .map(|(_, weight)| (scene, *weight))
+            })
+            .collect();
+        let total: f64 = weighted.iter().map(|(_, weight)| weight).sum();
+        if total <= 0.0 {
+            return;
+        }
+        let mean = |metric: SceneMetric| {
+            weighted
+                .iter()
+                .map(|(scene, weight)| scene.get_value(&metric) * weight)
+                .sum::<f64>()
+                / total
+        };
+        let scene = Scene {
+            scene_name: WEIGHTED_SCORE_SCENE.to_string(),
+            avg_cpu: mean(SceneMetric::CPU),
+            avg_fps: mean(SceneMetric::FPS),
+            avg_time: mean(SceneMetric::AvgTime),
+            render_time: mean(SceneMetric::RenderTime),
+            flush_time: mean(SceneMetric::FlushTime),
+            extra_metrics: BTreeMap::new(),
+            derived: DerivedMetrics {
+                render_share: mean(SceneMetric::RenderShare),
+                frame_headroom: mean(SceneMetric::FrameHeadroom),
+                score: mean(SceneMetric::Score),
+            },
+        };
+        self.scenes.push(scene);
+    }
+
+    /// Looks up a metadata value, ignoring case, whitespace and punctuation in the key.
+    pub fn get_metadata(&self, key: &str) -> Option<&str> {
+        let key = normalize_column_name(key);
+        self.metadata
+            .iter()
+            .find(|(k, _)| normalize_column_name(k) == key)
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn label(&self) -> Option<&str> {
+        self.get_metadata("label")
+    }
+
+    pub fn resolution(&self) -> Option<&str> {
+        self.get_metadata("resolution")
+    }
+
+    /// Human readable name of the run, built from its label and resolution.
+    pub fn title(&self) -> Option<String> {
+        match (self.label(), self.resolution()) {
+            (Some(label), Some(resolution)) => Some(format!("{} ({})", label, resolution)),
+            (Some(label), None) => Some(label.to_string()),
+            (None, Some(resolution)) => Some(resolution.to_string()),
+            (None, None) => None,
+        }
+    }
+
+    /// Whether both runs were produced by the same benchmark configuration.
+    pub fn same_config(&self, other: &BenchmarkRun) -> bool {
+        self.label() == other.label() && self.resolution() == other.resolution()
+    }
+
+    /// Describes every metadata entry (including the LVGL version) that differs from `baseline`.
+    pub fn compare_metadata(&self, baseline: &BenchmarkRun) -> Vec<String> {
+        fn describe(value: Option<&str>) -> &str {
+            value.unwrap_or("(none)")
+        }
+
+        let mut mismatches = Vec::new();
+        if self.version != baseline.version {
+            mismatches.push(format!(
+                "LVGL version: {} → {}",
+                describe(baseline.version.as_deref()),
+                describe(self.version.as_deref())
+            ));
+        }
+        let keys = baseline
+            .metadata
+            .iter()
+            .chain(self.metadata.iter())
+            .map(|(key, _)| key);
+        let mut seen = Vec::new();
+        for key in keys {
+            let normalized = normalize_column_name(key);
+            if seen.contains(&normalized) {
+                continue;
+            }
+            seen.push(normalized);
+            let (baseline_value, value) = (baseline.get_metadata(key), self.get_metadata(key));
+            if baseline_value != value {
+                mismatches.push(format!(
+                    "{}: {} → {}",
+                    key,
+                    describe(baseline_value),
+                    describe(value)
+                ));
+            }
+        }
+        mismatches
+    }
+
+    /// Single line summary of the version and metadata, `None` if there's nothing to show.
+    pub fn metadata_summary(&self) -> Option<String> {
+        let entries: Vec<String> = self
+            .version
+            .iter()
+            .map(|version| format!("**LVGL** {}", version))
+            .chain(
+                self.metadata
+                    .iter()
+                    .map(|(key, value)| format!("**{}** {}", key, value)),
+            )
+            .collect();
+        (!entries.is_empty()).then(|| entries.join(" | "))
+    }
+}
+
 impl SceneMetric {
+    pub const ALL: [SceneMetric; 8] = [
+        SceneMetric::CPU,
+        SceneMetric::FPS,
+        SceneMetric::AvgTime,
+        SceneMetric::RenderTime,
+        SceneMetric::FlushTime,
+        SceneMetric::RenderShare,
+        SceneMetric::FrameHeadroom,
+        SceneMetric::Score,
+    ];
+
+    /// Metrics read from the `Benchmark Summary` columns.
+    pub const PARSED: [SceneMetric; 5] = [
+        SceneMetric::CPU,
+        SceneMetric::FPS,
+        SceneMetric::AvgTime,
+        SceneMetric::RenderTime,
+        SceneMetric::FlushTime,
+    ];
+
+    /// Whether the metric is computed from the parsed ones, see [`DerivedMetrics`].
+    pub fn is_derived(&self) -> bool {
+        !SceneMetric::PARSED.contains(self)
+    }
+
+    pub fn direction(&self) -> MetricDirection {
+        match self {
+            SceneMetric::FPS | SceneMetric::FrameHeadroom | SceneMetric::Score => {
+                MetricDirection::HigherIsBetter
+            }
+            SceneMetric::CPU
+            | SceneMetric::AvgTime
+            | SceneMetric::RenderTime
+            | SceneMetric::FlushTime
+            | SceneMetric::RenderShare => MetricDirection::LowerIsBetter,
+        }
+    }
+
     pub fn label(&self) -> &'static str {
         match self {
             SceneMetric::FPS => "FPS",
@@ -41,6 +437,9 @@ impl SceneMetric {
             SceneMetric::AvgTime => "Average Time (ms)",
             SceneMetric::RenderTime => "Render Time (ms)",
             SceneMetric::FlushTime => "Flush Time (ms)",
+            SceneMetric::RenderShare => "Render Share (%)",
+            SceneMetric::FrameHeadroom => "Frame Headroom (ms)",
+            SceneMetric::Score => "Score (%)",
         }
     }
 
@@ -51,7 +450,107 @@ impl SceneMetric {
             SceneMetric::AvgTime => "avg_time",
             SceneMetric::RenderTime => "render_time",
             SceneMetric::FlushTime => "flush_time",
+            SceneMetric::RenderShare => "render_share",
+            SceneMetric::FrameHeadroom => "frame_headroom",
+            SceneMetric::Score => "score",
+        }
+    }
+
+    /// The column name LVGL prints in the `Benchmark Summary` header.
+    ///
+    /// Derived metrics aren't printed, their label is used instead.
+    pub fn column_name(&self) -> &'static str {
+        match self {
+            SceneMetric::FPS => "Avg. FPS",
+            SceneMetric::CPU => "Avg. CPU",
+            SceneMetric::AvgTime => "Avg. time",
+            SceneMetric::RenderTime => "render time",
+            SceneMetric::FlushTime => "flush time",
+            SceneMetric::RenderShare | SceneMetric::FrameHeadroom | SceneMetric::Score => {
+                self.label()
+            }
+        }
+    }
+
+    /// Maps a `Benchmark Summary` header column to a metric, ignoring case, whitespace and punctuation.
+    pub fn from_column_name(name: &str) -> Option<Self> {
+        match normalize_column_name(name).as_str() {
+            "avgcpu" | "cpu" => Some(SceneMetric::CPU),
+            "avgfps" | "fps" => Some(SceneMetric::FPS),
+            "avgtime" | "time" => Some(SceneMetric::AvgTime),
+            "rendertime" | "avgrendertime" => Some(SceneMetric::RenderTime),
+            "flushtime" | "avgflushtime" => Some(SceneMetric::FlushTime),
+            _ => None,
+        }
+    }
+}
+
+/// Name of the scene added by [`BenchmarkRun::add_weighted_scene`].
+pub const WEIGHTED_SCORE_SCENE: &str = "Weighted score";
+
+/// Lowercases `name` and strips everything that isn't alphanumeric.
+pub fn normalize_column_name(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_alphanumeric())
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+/// Lowercases `name` and separates its words with single spaces, dropping punctuation.
+///
+/// `"All scenes avg."` and `"all  scenes avg"` are both normalized to `"all scenes avg"`.
+pub fn normalize_scene_name(name: &str) -> String {
+    name.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| word.to_lowercase())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Scene names that changed between LVGL versions, from their old name to their new name.
+///
+/// Names are normalized with [`normalize_scene_name`] before being looked up.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct SceneAliases(BTreeMap<String, String>);
+
+impl SceneAliases {
+    /// The normalized name shared by every alias of a scene.
+    pub fn canonical(&self, name: &str) -> String {
+        let normalized = normalize_scene_name(name);
+        self.0
+            .iter()
+            .find(|(alias, _)| normalize_scene_name(alias) == normalized)
+            .map(|(_, name)| normalize_scene_name(name))
+            .unwrap_or(normalized)
+    }
+
+    pub fn same_scene(&self, a: &str, b: &str) -> bool {
+        self.canonical(a) == self.canonical(b)
+    }
+
+    /// Every name of a scene: the name itself, its normalized name and the aliases of the scene.
+    pub fn names(&self, name: &str) -> Vec<String> {
+        let canonical = self.canonical(name);
+        let mut names = vec![
+            name.to_string(),
+            normalize_scene_name(name),
+            canonical.clone(),
+        ];
+        for (alias, aliased) in &self.0 {
+            if normalize_scene_name(aliased) == canonical {
+                names.extend([alias.clone(), aliased.clone()]);
+            }
         }
+        let mut seen = HashSet::new();
+        names.retain(|name| seen.insert(name.clone()));
+        names
+    }
+}
+
+impl FromIterator<(String, String)> for SceneAliases {
+    fn from_iter<T: IntoIterator<Item = (String, String)>>(iter: T) -> Self {
+        Self(iter.into_iter().collect())
     }
 }
 
@@ -65,7 +564,217 @@ impl FromStr for SceneMetric {
             "avg_time" => Ok(SceneMetric::AvgTime),
             "render_time" => Ok(SceneMetric::RenderTime),
             "flush_time" => Ok(SceneMetric::FlushTime),
+            "render_share" => Ok(SceneMetric::RenderShare),
+            "frame_headroom" => Ok(SceneMetric::FrameHeadroom),
+            "score" => Ok(SceneMetric::Score),
             _ => Err(Error::InvalidMetric(s.to_string())),
         }
     }
 }
+
+/// Formats a metric value with up to two decimal places, dropping trailing zeros.
+///
+/// `61.0` is formatted as `61` and `3.250` as `3.25`.
+pub fn format_value(value: f64) -> String {
+    let formatted = format!("{:.2}", value);
+    let formatted = formatted.trim_end_matches('0').trim_end_matches('.');
+    if formatted == "-0" {
+        "0".to_string()
+    } else {
+        formatted.to_string()
+    }
+}
+
+/// Formats a metric delta like [`format_value`] but always with a sign.
+pub fn format_delta(delta: f64) -> String {
+    let formatted = format_value(delta);
+    if formatted.starts_with('-') {
+        formatted
+    } else {
+        format!("+{}", formatted)
+    }
+}
+
+/// Formats a relative change with one decimal place and a sign, like `+12.3%`.
+pub fn format_percentage(percentage: f64) -> String {
+    let formatted = format!("{:+.1}%", percentage);
+    if formatted == "-0.0%" {
+        "+0.0%".to_string()
+    } else {
+        formatted
+    }
+}
+
+/// Whether a delta is displayed as zero once rounded to two decimal places.
+pub fn is_zero_delta(delta: f64) -> bool {
+    format_value(delta) == "0"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_value() {
+        assert_eq!(format_value(61.0), "61");
+        assert_eq!(format_value(3.25), "3.25");
+        assert_eq!(format_value(12.5), "12.5");
+        assert_eq!(format_value(0.004), "0");
+        assert_eq!(format_value(-0.001), "0");
+        assert_eq!(format_value(-1.333), "-1.33");
+    }
+
+    #[test]
+    fn test_format_delta() {
+        assert_eq!(format_delta(5.0), "+5");
+        assert_eq!(format_delta(-3.0), "-3");
+        assert_eq!(format_delta(0.25), "+0.25");
+        assert!(is_zero_delta(0.001));
+        assert_eq!(format_percentage(12.345), "+12.3%");
+        assert_eq!(format_percentage(-4.0), "-4.0%");
+        assert_eq!(format_percentage(-0.01), "+0.0%");
+        assert!(!is_zero_delta(0.01));
+    }
+
+    #[test]
+    fn test_aggregate_runs() {
+        let scene = |avg_fps: f64| Scene {
+            scene_name: "Empty screen".to_string(),
+            avg_cpu: 30.0,
+            avg_fps,
+            avg_time: 4.0,
+            render_time: 0.0,
+            flush_time: 4.0,
+            extra_metrics: BTreeMap::new(),
+            derived: DerivedMetrics::default(),
+        };
+        let runs: Vec<BenchmarkRun> = [56.0, 60.0, 61.0]
+            .into_iter()
+            .map(|fps| BenchmarkRun::new(vec![scene(fps)]))
+            .collect();
+
+        let aggregated = BenchmarkRun::aggregate(&runs);
+        assert_eq!(aggregated.repetitions(), 3);
+        assert_eq!(aggregated.scenes[0].avg_fps, 59.0);
+        assert_eq!(aggregated.scenes[0].avg_cpu, 30.0);
+        let stats = aggregated.aggregates[0].get(&SceneMetric::FPS);
+        assert_eq!(stats.median, 60.0);
+        assert_eq!(stats.min, 56.0);
+        assert_eq!(stats.max, 61.0);
+
+        assert_eq!(BenchmarkRun::aggregate(&runs[..1]), runs[0]);
+    }
+
+    #[test]
+    fn test_aggregate_runs_missing_scenes() {
+        let scene = |name: &str, avg_fps: f64| Scene {
+            scene_name: name.to_string(),
+            avg_cpu: 30.0,
+            avg_fps,
+            avg_time: 4.0,
+            render_time: 0.0,
+            flush_time: 4.0,
+            extra_metrics: BTreeMap::new(),
+            derived: DerivedMetrics::default(),
+        };
+        let runs = vec![
+            BenchmarkRun::new(vec![scene("Empty screen", 56.0)]),
+            BenchmarkRun::new(vec![
+                scene("Empty screen", 60.0),
+                scene("Widgets demo", 20.0),
+            ]),
+            BenchmarkRun::new(vec![scene("Widgets demo", 30.0)]),
+        ];
+
+        let aggregated = BenchmarkRun::aggregate(&runs);
+        let names: Vec<&str> = aggregated
+            .scenes
+            .iter()
+            .map(|scene| scene.scene_name.as_str())
+            .collect();
+        assert_eq!(names, vec!["Empty screen", "Widgets demo"]);
+        assert_eq!(aggregated.scenes[0].avg_fps, 58.0);
+        assert_eq!(aggregated.scenes[1].avg_fps, 25.0);
+        assert_eq!(aggregated.aggregates[1].avg_fps.samples.len(), 2);
+    }
+
+    #[test]
+    fn test_derive_metrics() {
+        let scene = |name: &str, avg_fps: f64, avg_time: f64| {
+            let mut scene = Scene {
+                scene_name: name.to_string(),
+                avg_cpu: 40.0,
+                avg_fps,
+                avg_time,
+                render_time: avg_time / 4.0,
+                flush_time: avg_time * 3.0 / 4.0,
+                extra_metrics: BTreeMap::new(),
+                derived: DerivedMetrics::default(),
+            };
+            scene.derive_metrics(50.0);
+            scene
+        };
+        let empty = scene("Empty screen", 50.0, 8.0);
+        assert_eq!(empty.get_value(&SceneMetric::RenderShare), 25.0);
+        assert_eq!(empty.get_value(&SceneMetric::FrameHeadroom), 12.0);
+        assert_eq!(empty.get_value(&SceneMetric::Score), 100.0);
+        assert_eq!(
+            scene("Idle", 0.0, 0.0).get_value(&SceneMetric::RenderShare),
+            0.0
+        );
+
+        let mut run = BenchmarkRun::new(vec![empty, scene("Widgets demo", 20.0, 24.0)]);
+        run.add_weighted_scene(&BTreeMap::new());
+        assert_eq!(run.scenes.len(), 2);
+
+        let weights = BTreeMap::from([
+            ("empty screen".to_string(), 1.0),
+            ("Widgets demo".to_string(), 3.0),
+        ]);
+        run.add_weighted_scene(&weights);
+        let weighted = &run.scenes[2];
+        assert_eq!(weighted.scene_name, WEIGHTED_SCORE_SCENE);
+        assert_eq!(weighted.avg_fps, 27.5);
+        assert_eq!(weighted.get_value(&SceneMetric::Score), 55.0);
+        assert_eq!(weighted.get_value(&SceneMetric::FrameHeadroom), 0.0);
+    }
+
+    #[test]
+    fn test_scene_aliases() {
+        assert_eq!(normalize_scene_name("All scenes avg."), "all scenes avg");
+        assert_eq!(
+            normalize_scene_name(" Multiple  ARGB images"),
+            "multiple argb images"
+        );
+
+        let aliases: SceneAliases = [(
+            "Multiple RGB images".to_string(),
+            "Multiple opaque images".to_string(),
+        )]
+        .into_iter()
+        .collect();
+        assert!(aliases.same_scene("All scenes avg.", "All scenes avg"));
+        assert!(aliases.same_scene("Multiple ARGB images", "Multiple argb images"));
+        assert!(aliases.same_scene("multiple rgb images", "Multiple opaque images"));
+        assert!(!aliases.same_scene("Multiple RGB images", "Multiple ARGB images"));
+    }
+
+    #[test]
+    fn test_scene_alias_names() {
+        let aliases: SceneAliases = [(
+            "Multiple RGB images".to_string(),
+            "Multiple opaque images".to_string(),
+        )]
+        .into_iter()
+        .collect();
+        assert_eq!(
+            aliases.names("Multiple opaque images"),
+            [
+                "Multiple opaque images",
+                "multiple opaque images",
+                "Multiple RGB images"
+            ]
+        );
+        assert_eq!(aliases.names("empty screen"), ["empty screen"]);
+    }
+}
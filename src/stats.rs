@@ -0,0 +1,358 @@
+//! Statistics over repeated benchmark samples.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// Statistics of a metric over repeated runs.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MetricStats {
+    pub samples: Vec<f64>,
+    pub mean: f64,
+    pub median: f64,
+    /// Sample standard deviation, zero with less than two samples
+    pub std_dev: f64,
+    pub min: f64,
+    pub max: f64,
+    /// P-value of the difference with the baseline samples, filled when computing the delta
+    pub p_value: Option<f64>,
+}
+
+impl MetricStats {
+    pub fn from_samples(samples: Vec<f64>) -> Self {
+        Self {
+            mean: mean(&samples),
+            median: median(&samples),
+            std_dev: std_dev(&samples),
+            min: samples.iter().copied().fold(f64::INFINITY, f64::min),
+            max: samples.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+            p_value: None,
+            samples,
+        }
+    }
+}
+
+pub fn mean(samples: &[f64]) -> f64 {
+    if samples.is_empty() {
+        return 0.0;
+    }
+    samples.iter().sum::<f64>() / samples.len() as f64
+}
+
+pub fn median(samples: &[f64]) -> f64 {
+    if samples.is_empty() {
+        return 0.0;
+    }
+    let mut sorted = samples.to_vec();
+    sorted.sort_by(f64::total_cmp);
+    let middle = sorted.len() / 2;
+    if sorted.len().is_multiple_of(2) {
+        (sorted[middle - 1] + sorted[middle]) / 2.0
+    } else {
+        sorted[middle]
+    }
+}
+
+/// Unbiased sample variance (divided by `n - 1`).
+pub fn variance(samples: &[f64]) -> f64 {
+    if samples.len() < 2 {
+        return 0.0;
+    }
+    let mean = mean(samples);
+    samples.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / (samples.len() - 1) as f64
+}
+
+pub fn std_dev(samples: &[f64]) -> f64 {
+    variance(samples).sqrt()
+}
+
+/// Fraction of the samples dropped at each end by [`trimmed_mean`].
+pub const TRIM_FRACTION: f64 = 0.2;
+
+/// Mean of the samples without the lowest and highest `fraction` of them.
+pub fn trimmed_mean(samples: &[f64], fraction: f64) -> f64 {
+    let mut sorted = samples.to_vec();
+    sorted.sort_by(f64::total_cmp);
+    let trimmed = (sorted.len() as f64 * fraction).floor() as usize;
+    mean(&sorted[trimmed..sorted.len() - trimmed])
+}
+
+/// Statistic representing the samples of a combined baseline.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BaselineStatistic {
+    #[default]
+    Mean,
+    Median,
+    TrimmedMean,
+}
+
+impl BaselineStatistic {
+    pub fn of(&self, stats: &MetricStats) -> f64 {
+        match self {
+            BaselineStatistic::Mean => stats.mean,
+            BaselineStatistic::Median => stats.median,
+            BaselineStatistic::TrimmedMean => trimmed_mean(&stats.samples, TRIM_FRACTION),
+        }
+    }
+}
+
+impl fmt::Display for BaselineStatistic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BaselineStatistic::Mean => write!(f, "mean"),
+            BaselineStatistic::Median => write!(f, "median"),
+            BaselineStatistic::TrimmedMean => write!(f, "trimmed mean"),
+        }
+    }
+}
+
+/// Statistical test used to compare baseline and PR samples.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SignificanceTest {
+    #[default]
+    Welch,
+    MannWhitney,
+}
+
+impl SignificanceTest {
+    /// Two-sided p-value of the difference between both samples.
+    ///
+    /// Returns `None` when one of the samples has less than two values.
+    pub fn p_value(&self, a: &[f64], b: &[f64]) -> Option<f64> {
+        if a.len() < 2 || b.len() < 2 {
+            return None;
+        }
+        Some(match self {
+            SignificanceTest::Welch => welch_t_test(a, b),
+            SignificanceTest::MannWhitney => mann_whitney_u_test(a, b),
+        })
+    }
+
+    /// Smallest p-value the test can return with samples of `n_a` and `n_b` values.
+    ///
+    /// The Mann-Whitney U test only depends on the ranks, so with 2 or 3 values per sample even
+    /// completely separated samples aren't significant at the usual levels.
+    pub fn min_p_value(&self, n_a: usize, n_b: usize) -> f64 {
+        match self {
+            SignificanceTest::Welch => 0.0,
+            SignificanceTest::MannWhitney => {
+                let a: Vec<f64> = (0..n_a).map(|x| x as f64).collect();
+                let b: Vec<f64> = (n_a..n_a + n_b).map(|x| x as f64).collect();
+                mann_whitney_u_test(&a, &b)
+            }
+        }
+    }
+}
+
+/// Two-sided p-value of Welch's t-test.
+pub fn welch_t_test(a: &[f64], b: &[f64]) -> f64 {
+    let (n_a, n_b) = (a.len() as f64, b.len() as f64);
+    let (var_a, var_b) = (variance(a) / n_a, variance(b) / n_b);
+    let mean_difference = mean(a) - mean(b);
+    if var_a + var_b == 0.0 {
+        return if mean_difference == 0.0 { 1.0 } else { 0.0 };
+    }
+    let t = mean_difference / (var_a + var_b).sqrt();
+    let df = (var_a + var_b).powi(2) / (var_a.powi(2) / (n_a - 1.0) + var_b.powi(2) / (n_b - 1.0));
+    incomplete_beta(df / 2.0, 0.5, df / (df + t * t))
+}
+
+/// Two-sided p-value of the Mann-Whitney U test, using the normal approximation with tie and
+/// continuity corrections.
+pub fn mann_whitney_u_test(a: &[f64], b: &[f64]) -> f64 {
+    let mut values: Vec<(f64, bool)> = a
+        .iter()
+        .map(|&x| (x, true))
+        .chain(b.iter().map(|&x| (x, false)))
+        .collect();
+    values.sort_by(|x, y| x.0.total_cmp(&y.0));
+
+    let n = values.len() as f64;
+    let mut rank_sum_a = 0.0;
+    let mut tie_correction = 0.0;
+    let mut i = 0;
+    while i < values.len() {
+        let mut j = i;
+        while j + 1 < values.len() && values[j + 1].0 == values[i].0 {
+            j += 1;
+        }
+        // Tied values share the average of their ranks
+        let rank = (i + j) as f64 / 2.0 + 1.0;
+        let ties = (j - i + 1) as f64;
+        rank_sum_a += rank * values[i..=j].iter().filter(|(_, in_a)| *in_a).count() as f64;
+        tie_correction += ties.powi(3) - ties;
+        i = j + 1;
+    }
+
+    let (n_a, n_b) = (a.len() as f64, b.len() as f64);
+    let u = rank_sum_a - n_a * (n_a + 1.0) / 2.0;
+    let mean_u = n_a * n_b / 2.0;
+    let sigma = (n_a * n_b / 12.0 * ((n + 1.0) - tie_correction / (n * (n - 1.0)))).sqrt();
+    if sigma == 0.0 {
+        return 1.0;
+    }
+    let z = ((u - mean_u).abs() - 0.5).max(0.0) / sigma;
+    erfc(z / std::f64::consts::SQRT_2)
+}
+
+/// Complementary error function, with a fractional error below 1.2e-7.
+fn erfc(x: f64) -> f64 {
+    let z = x.abs();
+    let t = 1.0 / (1.0 + 0.5 * z);
+    let polynomial = [
+        -1.26551223,
+        1.00002368,
+        0.37409196,
+        0.09678418,
+        -0.18628806,
+        0.27886807,
+        -1.13520398,
+        1.48851587,
+        -0.82215223,
+        0.17087277,
+    ]
+    .iter()
+    .rev()
+    .fold(0.0, |acc, coefficient| acc * t + coefficient);
+    let result = t * (-z * z + polynomial).exp();
+    if x >= 0.0 { result } else { 2.0 - result }
+}
+
+/// Natural logarithm of the gamma function (Lanczos approximation).
+fn ln_gamma(x: f64) -> f64 {
+    const COEFFICIENTS: [f64; 6] = [
+        76.18009172947146,
+        -86.50532032941677,
+        24.01409824083091,
+        -1.231739572450155,
+        0.1208650973866179e-2,
+        -0.5395239384953e-5,
+    ];
+    let tmp = x + 5.5;
+    let tmp = tmp - (x + 0.5) * tmp.ln();
+    let series = COEFFICIENTS
+        .iter()
+        .enumerate()
+        .fold(1.000000000190015, |acc, (i, c)| {
+            acc + c / (x + 1.0 + i as f64)
+        });
+    -tmp + (2.5066282746310005 * series / x).ln()
+}
+
+/// Regularized incomplete beta function `I_x(a, b)`.
+fn incomplete_beta(a: f64, b: f64, x: f64) -> f64 {
+    if x <= 0.0 {
+        return 0.0;
+    }
+    if x >= 1.0 {
+        return 1.0;
+    }
+    let front =
+        (ln_gamma(a + b) - ln_gamma(a) - ln_gamma(b) + a * x.ln() + b * (1.0 - x).ln()).exp();
+    if x < (a + 1.0) / (a + b + 2.0) {
+        front * beta_continued_fraction(a, b, x) / a
+    } else {
+        1.0 - front * beta_continued_fraction(b, a, 1.0 - x) / b
+    }
+}
+
+fn beta_continued_fraction(a: f64, b: f64, x: f64) -> f64 {
+    const MAX_ITERATIONS: usize = 200;
+    const EPSILON: f64 = 3e-14;
+    const TINY: f64 = 1e-300;
+
+    let clamp = |value: f64| if value.abs() < TINY { TINY } else { value };
+    let mut c = 1.0;
+    let mut d = 1.0 / clamp(1.0 - (a + b) * x / (a + 1.0));
+    let mut h = d;
+    for m in 1..=MAX_ITERATIONS {
+        let m = m as f64;
+        let m2 = 2.0 * m;
+        let even = m * (b - m) * x / ((a + m2 - 1.0) * (a + m2));
+        d = 1.0 / clamp(1.0 + even * d);
+        c = clamp(1.0 + even / c);
+        h *= d * c;
+        let odd = -(a + m) * (a + b + m) * x / ((a + m2) * (a + m2 + 1.0));
+        d = 1.0 / clamp(1.0 + odd * d);
+        c = clamp(1.0 + odd / c);
+        let step = d * c;
+        h *= step;
+        if (step - 1.0).abs() < EPSILON {
+            break;
+        }
+    }
+    h
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_metric_stats() {
+        let stats = MetricStats::from_samples(vec![2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0]);
+        assert_eq!(stats.mean, 5.0);
+        assert_eq!(stats.median, 4.5);
+        assert!((stats.std_dev - 2.138).abs() < 1e-3);
+        assert_eq!(stats.min, 2.0);
+        assert_eq!(stats.max, 9.0);
+    }
+
+    #[test]
+    fn test_single_sample() {
+        let stats = MetricStats::from_samples(vec![61.0]);
+        assert_eq!(stats.mean, 61.0);
+        assert_eq!(stats.median, 61.0);
+        assert_eq!(stats.std_dev, 0.0);
+        assert_eq!(median(&[3.0, 1.0, 2.0]), 2.0);
+    }
+
+    #[test]
+    fn test_baseline_statistic() {
+        let stats = MetricStats::from_samples(vec![10.0, 61.0, 60.0, 59.0, 100.0]);
+        assert_eq!(BaselineStatistic::Mean.of(&stats), 58.0);
+        assert_eq!(BaselineStatistic::Median.of(&stats), 60.0);
+        assert_eq!(BaselineStatistic::TrimmedMean.of(&stats), 60.0);
+        assert_eq!(trimmed_mean(&[1.0, 2.0], TRIM_FRACTION), 1.5);
+    }
+
+    #[test]
+    fn test_welch_t_test() {
+        let a = [1.0, 2.0, 3.0, 4.0, 5.0];
+        let b = [6.0, 7.0, 8.0, 9.0, 10.0];
+        assert!((welch_t_test(&a, &b) - 0.001053).abs() < 1e-5);
+        assert!((welch_t_test(&a, &a) - 1.0).abs() < 1e-9);
+        assert_eq!(welch_t_test(&[1.0, 1.0], &[2.0, 2.0]), 0.0);
+    }
+
+    #[test]
+    fn test_mann_whitney_u_test() {
+        let a = [1.0, 2.0, 3.0, 4.0, 5.0];
+        let b = [6.0, 7.0, 8.0, 9.0, 10.0];
+        assert!((mann_whitney_u_test(&a, &b) - 0.01219).abs() < 1e-4);
+        assert_eq!(mann_whitney_u_test(&[3.0, 3.0], &[3.0, 3.0]), 1.0);
+    }
+
+    #[test]
+    fn test_min_p_value() {
+        assert_eq!(SignificanceTest::Welch.min_p_value(2, 2), 0.0);
+        assert!(SignificanceTest::MannWhitney.min_p_value(3, 3) > 0.05);
+        assert!(SignificanceTest::MannWhitney.min_p_value(4, 4) < 0.05);
+        assert_eq!(
+            SignificanceTest::MannWhitney.min_p_value(3, 3),
+            mann_whitney_u_test(&[1.0, 2.0, 3.0], &[10.0, 11.0, 12.0])
+        );
+    }
+
+    #[test]
+    fn test_p_value_needs_two_samples() {
+        assert_eq!(SignificanceTest::Welch.p_value(&[1.0], &[2.0, 3.0]), None);
+        assert!(
+            SignificanceTest::MannWhitney
+                .p_value(&[1.0, 2.0], &[2.0, 3.0])
+                .is_some()
+        );
+    }
+}